@@ -1,5 +1,6 @@
 use crate::piece::{Piece, PieceType};
 use crate::Color;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 pub struct Square {
@@ -9,104 +10,551 @@ pub struct Square {
 #[derive(Debug, Clone)]
 pub struct Board {
     pub squares: Vec<Vec<Square>>,
-    pub white_king_square: (u8, u8),
-    pub black_king_square: (u8, u8),
     pub current_turn: Color,
     pub in_check: Color,
+    // squares of the rooks that can still castle
+    pub castling_rooks: Vec<(u8, u8)>,
+    // squares of pieces that started out as pawns
+    pub promoted_squares: Vec<(u8, u8)>,
+    // whether captured pieces go into the capturer's pocket and can be dropped back on, like in Crazyhouse
+    pub drops: bool,
+    // whether white pawns on the first rank can move two squares, like in Horde
+    pub first_rank_double_steps: bool,
+    pub white_pocket: Vec<PieceType>,
+    pub black_pocket: Vec<PieceType>,
 }
 
 impl Board {
-    pub fn get_king_pos(&mut self) {
+    // a side can have any number of kings, like none in Horde or several in Antichess
+    pub fn king_squares(&self, color: Color) -> Vec<(u8, u8)> {
+        let mut squares: Vec<(u8, u8)> = Vec::new();
         for row in 0..self.squares.len() {
             for col in 0..self.squares[row].len() {
                 let piece = &self.squares[row][col].piece;
-                let piece_type = &piece.piece_type;
-                if piece_type == &PieceType::King {
-                    if piece.color == Color::White {
-                        self.white_king_square = (row as u8, col as u8);
-                        //println!("White king square: {:?}", self.white_king_square);
-                    } else {
-                        self.black_king_square = (row as u8, col as u8);
-                    }
+                if piece.piece_type == PieceType::King && piece.color == color {
+                    squares.push((row as u8, col as u8));
                 }
             }
         }
+        squares
+    }
+    // the column of the king on its starting rank, which is the one that can castle
+    fn castling_king_col(&self, color: Color) -> Option<u8> {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        self.king_squares(color)
+            .iter()
+            .find(|&&(row, _)| row == back_rank)
+            .map(|&(_, col)| col)
     }
     pub fn putting_in_check(&mut self) -> bool {
-        self.get_king_pos();
-        for row in 0..self.squares.len() {
-            for col in 0..self.squares[row].len() {
-                let piece = &self.squares[row][col].piece;
-                let piece_type = &piece.piece_type;
-
-                match &piece_type {
-                    PieceType::Pawn => {
-                        let mut attack_row = 0;
-                        let attacked_color;
-                        let mut pieces: Vec<&Piece> = Vec::new();
-
-                        if piece.color == Color::White {
-                            attacked_color = Color::Black;
-
-                            // not in top row
-                            if row <= 6 {
-                                attack_row = row + 1;
-                            }
-                            // Color::Black
-                        } else {
-                            attacked_color = Color::White;
-                            // not on bottom row
-                            if row >= 1 {
-                                attack_row = row - 1;
-                            }
+        self.in_check = Color::None;
+        for color in [Color::White, Color::Black] {
+            if self.is_in_check(color) {
+                self.in_check = color;
+                println!("In check: {:?}", self.in_check);
+            }
+        }
+        self.in_check != Color::None
+    }
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let enemy = if color == Color::White {
+            Color::Black
+        } else {
+            Color::White
+        };
+        self.king_squares(color)
+            .iter()
+            .any(|&(row, col)| self.is_attacked(row, col, enemy))
+    }
+    // every (from, to) move the side to move's pieces can make, without looking at whether it leaves a king in check
+    pub fn possible_moves(&self) -> Vec<((u8, u8), (u8, u8))> {
+        let mut moves: Vec<((u8, u8), (u8, u8))> = Vec::new();
+        for from_row in 0..8 {
+            for from_col in 0..8 {
+                let piece = &self.squares[from_row as usize][from_col as usize].piece;
+                if piece.piece_type == PieceType::None || piece.color != self.current_turn {
+                    continue;
+                }
+                for to_row in 0..8 {
+                    for to_col in 0..8 {
+                        // can_move complains about taking your own piece, so skip those squares
+                        let target = &self.squares[to_row as usize][to_col as usize].piece;
+                        if target.piece_type != PieceType::None && target.color == piece.color {
+                            continue;
                         }
-
-                        // not on very left
-                        if col >= 1 {
-                            let left_piece = &self.squares[attack_row][col - 1].piece;
-                            pieces.push(left_piece);
+                        if piece.can_move((from_row, from_col), (to_row, to_col), self) {
+                            moves.push(((from_row, from_col), (to_row, to_col)));
                         }
-                        // not on very right
-                        if col <= 6 {
-                            let right_piece = &self.squares[attack_row][col + 1].piece;
-                            pieces.push(right_piece);
+                    }
+                }
+            }
+        }
+        moves
+    }
+    // a copy of the board with a piece moved, without checking that the move is allowed
+    // castling moves the rook too
+    pub fn with_move(&self, from: (u8, u8), to: (u8, u8)) -> Board {
+        let mut board = self.clone();
+        let piece = board.squares[from.0 as usize][from.1 as usize]
+            .piece
+            .clone();
+        if piece.piece_type == PieceType::King {
+            if let Some(rook) = self.castling_rook(from, to) {
+                return self.with_castle(from, rook);
+            }
+        }
+        board.squares[from.0 as usize][from.1 as usize].piece = Piece {
+            piece_type: PieceType::None,
+            color: Color::None,
+        };
+        board.squares[to.0 as usize][to.1 as usize].piece = piece;
+        board
+    }
+    // a copy of the board after castling, without checking that castling is allowed
+    pub fn with_castle(&self, king: (u8, u8), rook: (u8, u8)) -> Board {
+        let mut board = self.clone();
+        let row = king.0 as usize;
+        let (king_to, rook_to) = castling_landing_cols(king.1, rook.1);
+        let color = board.squares[row][king.1 as usize].piece.color;
+
+        // clear both first, since in Chess960 they can land on each other's squares
+        for col in [king.1, rook.1] {
+            board.squares[row][col as usize].piece = Piece {
+                piece_type: PieceType::None,
+                color: Color::None,
+            };
+        }
+        board.squares[row][king_to as usize].piece = Piece {
+            piece_type: PieceType::King,
+            color,
+        };
+        board.squares[row][rook_to as usize].piece = Piece {
+            piece_type: PieceType::Rook,
+            color,
+        };
+        board
+    }
+    // a copy of the board with a piece dropped, without checking that the drop is allowed
+    pub fn with_drop(&self, piece: &Piece, to: (u8, u8)) -> Board {
+        let mut board = self.clone();
+        board.squares[to.0 as usize][to.1 as usize].piece = piece.clone();
+        board
+    }
+    pub fn pocket(&self, color: Color) -> &Vec<PieceType> {
+        if color == Color::White {
+            &self.white_pocket
+        } else {
+            &self.black_pocket
+        }
+    }
+    pub fn pocket_mut(&mut self, color: Color) -> &mut Vec<PieceType> {
+        if color == Color::White {
+            &mut self.white_pocket
+        } else {
+            &mut self.black_pocket
+        }
+    }
+    // whether a color has enough pieces left that it could ever checkmate
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let mut material = 0;
+        for row in &self.squares {
+            for square in row {
+                let piece = &square.piece;
+                if piece.color != color {
+                    continue;
+                }
+                // a pawn can always promote
+                if piece.piece_type == PieceType::Pawn {
+                    return true;
+                }
+                material += piece.value();
+            }
+        }
+        for piece_type in self.pocket(color) {
+            if piece_type == &PieceType::Pawn {
+                return true;
+            }
+            material += Piece {
+                piece_type: piece_type.clone(),
+                color,
+            }
+            .value();
+        }
+        // a lone knight or bishop can't mate
+        material > 3
+    }
+    // whether any piece of the given color attacks the square
+    pub fn is_attacked(&self, row: u8, col: u8, by: Color) -> bool {
+        let is_piece = |row: i8, col: i8, piece_types: &[PieceType]| -> bool {
+            if !(0..8).contains(&row) || !(0..8).contains(&col) {
+                return false;
+            }
+            let piece = &self.squares[row as usize][col as usize].piece;
+            piece.color == by && piece_types.contains(&piece.piece_type)
+        };
+        let (row, col) = (row as i8, col as i8);
+
+        // pawns attack diagonally forwards, so look one row back from their point of view
+        let pawn_row = if by == Color::White { row - 1 } else { row + 1 };
+        if is_piece(pawn_row, col - 1, &[PieceType::Pawn])
+            || is_piece(pawn_row, col + 1, &[PieceType::Pawn])
+        {
+            return true;
+        }
+
+        for (row_step, col_step) in KNIGHT_JUMPS {
+            if is_piece(row + row_step, col + col_step, &[PieceType::Knight]) {
+                return true;
+            }
+        }
+
+        for (row_step, col_step) in ALL_DIRECTIONS {
+            if is_piece(row + row_step, col + col_step, &[PieceType::King]) {
+                return true;
+            }
+
+            // rooks slide straight, bishops slide diagonally, queens do both
+            let sliders = if row_step == 0 || col_step == 0 {
+                [PieceType::Rook, PieceType::Queen]
+            } else {
+                [PieceType::Bishop, PieceType::Queen]
+            };
+
+            let mut ray_row = row + row_step;
+            let mut ray_col = col + col_step;
+            while (0..8).contains(&ray_row) && (0..8).contains(&ray_col) {
+                let piece = &self.squares[ray_row as usize][ray_col as usize].piece;
+                if piece.piece_type != PieceType::None {
+                    if piece.color == by && sliders.contains(&piece.piece_type) {
+                        return true;
+                    }
+                    break;
+                }
+                ray_row += row_step;
+                ray_col += col_step;
+            }
+        }
+
+        false
+    }
+    // works out which rook a king move castles with, if it is a castling move at all
+    // castling is written either as the king moving onto its own rook, or to its landing square
+    pub fn castling_rook(&self, from: (u8, u8), to: (u8, u8)) -> Option<(u8, u8)> {
+        let (from_row, from_col) = from;
+        let (to_row, to_col) = to;
+
+        // kings only castle along their own back rank
+        let back_rank = if self.squares[from_row as usize][from_col as usize]
+            .piece
+            .color
+            == Color::White
+        {
+            0
+        } else {
+            7
+        };
+        if from_row != back_rank || to_row != back_rank {
+            return None;
+        }
+
+        if self.castling_rooks.contains(&to) {
+            return Some(to);
+        }
+
+        // the king normally only moves one square, so two or more to the c or g file means castling
+        if to_col.abs_diff(from_col) < 2 {
+            return None;
+        }
+        let kingside = match to_col {
+            6 => true,
+            2 => false,
+            _ => return None,
+        };
+        self.castling_rooks
+            .iter()
+            .find(|&&(rook_row, rook_col)| {
+                rook_row == from_row && (rook_col > from_col) == kingside
+            })
+            .copied()
+    }
+    // the king and rook always land on the same squares as in standard chess,
+    // wherever they started, and every square either of them crosses must be empty
+    pub fn can_castle(&self, king: (u8, u8), rook: (u8, u8)) -> bool {
+        let (row, king_col) = king;
+        let rook_col = rook.1;
+        let color = self.squares[row as usize][king_col as usize].piece.color;
+        let enemy = if color == Color::White {
+            Color::Black
+        } else {
+            Color::White
+        };
+
+        let rook_piece = &self.squares[row as usize][rook_col as usize].piece;
+        if rook_piece.piece_type != PieceType::Rook || rook_piece.color != color {
+            return false;
+        }
+
+        let (king_to, rook_to) = castling_landing_cols(king_col, rook_col);
+
+        let left = king_col.min(rook_col).min(king_to).min(rook_to);
+        let right = king_col.max(rook_col).max(king_to).max(rook_to);
+        for col in left..=right {
+            if col == king_col || col == rook_col {
+                continue;
+            }
+            if self.squares[row as usize][col as usize].piece.piece_type != PieceType::None {
+                return false;
+            }
+        }
+
+        // can't castle out of check
+        if self.is_attacked(row, king_col, enemy) {
+            return false;
+        }
+        // or through or into it, which is looked at once both pieces have moved,
+        // since in Chess960 the rook can be what was shielding those squares
+        let after = self.with_castle(king, rook);
+        for col in king_col.min(king_to)..=king_col.max(king_to) {
+            if col != king_col && after.is_attacked(row, col, enemy) {
+                return false;
+            }
+        }
+
+        true
+    }
+    // writes the position as a FEN, with castling as KQkq where that isn't ambiguous (X-FEN),
+    // or always as rook files (Shredder-FEN)
+    pub fn to_fen(&self, shredder: bool) -> String {
+        let mut ranks: Vec<String> = Vec::new();
+        for row in (0..8).rev() {
+            let mut rank = "".to_string();
+            let mut empty = 0;
+            for col in 0..8 {
+                match piece_to_letter(&self.squares[row][col].piece) {
+                    Some(letter) => {
+                        if empty > 0 {
+                            rank.push_str(&empty.to_string());
+                            empty = 0;
                         }
-                        for piece in pieces {
-                            if piece.piece_type == PieceType::King {
-                                self.in_check = attacked_color;
-                                println!("In check: {:?}", self.in_check);
-                            }
+                        rank.push(letter);
+                        if self.promoted_squares.contains(&(row as u8, col as u8)) {
+                            rank.push('~');
                         }
                     }
-                    PieceType::Knight => continue,
-                    PieceType::Bishop => continue,
-                    PieceType::Rook => continue,
-                    PieceType::Queen => continue,
-                    _ => continue,
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            ranks.push(rank);
+        }
+
+        let turn = if self.current_turn == Color::White {
+            "w"
+        } else {
+            "b"
+        };
+
+        let mut castling = "".to_string();
+        for (row, color) in [(0, Color::White), (7, Color::Black)] {
+            let king_col = match self.castling_king_col(color) {
+                Some(king_col) => king_col,
+                None => continue,
+            };
+            let mut rook_cols: Vec<u8> = self
+                .castling_rooks
+                .iter()
+                .filter(|&&(rook_row, _)| rook_row == row)
+                .map(|&(_, rook_col)| rook_col)
+                .collect();
+            // kingside first
+            rook_cols.sort_by(|a, b| b.cmp(a));
+
+            for rook_col in rook_cols {
+                let is_own_rook = |col: u8| {
+                    let piece = &self.squares[row as usize][col as usize].piece;
+                    piece.piece_type == PieceType::Rook && piece.color == color
+                };
+                let kingside = rook_col > king_col;
+                let outermost = if kingside {
+                    !(rook_col + 1..8).any(is_own_rook)
+                } else {
+                    !(0..rook_col).any(is_own_rook)
+                };
+
+                let letter = match (shredder || !outermost, kingside) {
+                    (true, _) => (b'a' + rook_col) as char,
+                    (false, true) => 'k',
+                    (false, false) => 'q',
                 };
+                if row == 0 {
+                    castling.push(letter.to_ascii_uppercase());
+                } else {
+                    castling.push(letter);
+                }
             }
         }
-        // REMOVE LATER
-        false
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let mut placement = ranks.join("/");
+        if self.drops {
+            placement.push('[');
+            for (color, pocket) in [
+                (Color::White, &self.white_pocket),
+                (Color::Black, &self.black_pocket),
+            ] {
+                for piece_type in pocket {
+                    let piece = Piece {
+                        piece_type: piece_type.clone(),
+                        color,
+                    };
+                    if let Some(letter) = piece_to_letter(&piece) {
+                        placement.push(letter);
+                    }
+                }
+            }
+            placement.push(']');
+        }
+
+        // the board doesn't track en passant or move counters yet
+        format!("{} {} {} - 0 1", placement, turn, castling)
+    }
+}
+
+const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const ALL_DIRECTIONS: [(i8, i8); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// returns the king's and rook's landing columns
+pub fn castling_landing_cols(king_col: u8, rook_col: u8) -> (u8, u8) {
+    if rook_col > king_col {
+        // kingside, the king lands on g and the rook on f
+        (6, 5)
+    } else {
+        // queenside, the king lands on c and the rook on d
+        (2, 3)
     }
 }
 
+const STANDARD_BACK_RANK: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+// knight placements on the 5 squares left after the bishops and queen, in Chess960 numbering order
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
 pub fn create_board() -> Board {
+    create_board_from_back_rank(&STANDARD_BACK_RANK)
+}
+
+// creates one of the 960 Fischer Random starting positions, where 518 is the standard one
+pub fn create_chess960_board(index: u16) -> Result<Board, String> {
+    if index > 959 {
+        return Err(String::from("Chess960 positions go from 0 to 959."));
+    }
+
+    let mut back_rank = [
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+        PieceType::None,
+    ];
+    let mut index = index as usize;
+
+    // one bishop on a light square (b, d, f, h), one on a dark square (a, c, e, g)
+    back_rank[index % 4 * 2 + 1] = PieceType::Bishop;
+    index /= 4;
+    back_rank[index % 4 * 2] = PieceType::Bishop;
+    index /= 4;
+
+    let empty_cols = |back_rank: &[PieceType; 8]| -> Vec<usize> {
+        (0..8)
+            .filter(|&col| back_rank[col] == PieceType::None)
+            .collect()
+    };
+
+    let queen_col = empty_cols(&back_rank)[index % 6];
+    back_rank[queen_col] = PieceType::Queen;
+    index /= 6;
+
+    let (first_knight, second_knight) = CHESS960_KNIGHTS[index];
+    let cols = empty_cols(&back_rank);
+    back_rank[cols[first_knight]] = PieceType::Knight;
+    back_rank[cols[second_knight]] = PieceType::Knight;
+
+    // the king always goes between the rooks
+    let cols = empty_cols(&back_rank);
+    back_rank[cols[0]] = PieceType::Rook;
+    back_rank[cols[1]] = PieceType::King;
+    back_rank[cols[2]] = PieceType::Rook;
+
+    Ok(create_board_from_back_rank(&back_rank))
+}
+
+pub fn random_chess960_index() -> u16 {
+    // no need for a proper random number generator, the clock is random enough to pick a position
+    let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(time) => time.subsec_nanos(),
+        Err(_err) => 0,
+    };
+    (nanos % 960) as u16
+}
+
+fn create_board_from_back_rank(back_rank: &[PieceType; 8]) -> Board {
     // created nested Vecs that hold Squares
     let mut squares: Vec<Vec<Square>> = Vec::new();
 
     for row in 0..8 {
         let mut row_squares: Vec<Square> = Vec::new();
 
-        for col in 0..8 {
+        for back_rank_piece in back_rank {
             // set positions of each piece on the chessboard
-            let piece_type = match (row, col) {
-                (1, _) | (6, _) => PieceType::Pawn,
-                (0, 0) | (0, 7) | (7, 0) | (7, 7) => PieceType::Rook,
-                (0, 1) | (0, 6) | (7, 1) | (7, 6) => PieceType::Knight,
-                (0, 2) | (0, 5) | (7, 2) | (7, 5) => PieceType::Bishop,
-                (0, 3) | (7, 3) => PieceType::Queen,
-                (0, 4) | (7, 4) => PieceType::King,
+            let piece_type = match row {
+                1 | 6 => PieceType::Pawn,
+                0 | 7 => back_rank_piece.clone(),
                 _ => PieceType::None,
             };
 
@@ -132,13 +580,187 @@ pub fn create_board() -> Board {
     };
     */
 
+    let mut castling_rooks: Vec<(u8, u8)> = Vec::new();
+    for row in [0, 7] {
+        for col in 0..8 {
+            if back_rank[col as usize] == PieceType::Rook {
+                castling_rooks.push((row, col));
+            }
+        }
+    }
+
     // return a Board
     Board {
         squares,
-        white_king_square: (0, 4),
-        black_king_square: (7, 4),
         current_turn: Color::White,
         in_check: Color::None,
+        castling_rooks,
+        promoted_squares: Vec::new(),
+        drops: false,
+        first_rank_double_steps: false,
+        white_pocket: Vec::new(),
+        black_pocket: Vec::new(),
+    }
+}
+
+// reads a FEN, with castling written either as KQkq (X-FEN) or as rook files like HAha (Shredder-FEN)
+// the board doesn't track en passant or move counters, so those fields are ignored
+pub fn create_board_from_fen(fen: &str) -> Result<Board, String> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() < 3 {
+        return Err(String::from(
+            "A FEN needs at least the pieces, side to move and castling.",
+        ));
+    }
+
+    let (placement, pocket) = match fields[0].split_once('[') {
+        Some((placement, pocket)) => match pocket.strip_suffix(']') {
+            Some(pocket) => (placement, Some(pocket)),
+            None => return Err(String::from("FEN pocket is missing its closing ].")),
+        },
+        None => (fields[0], None),
+    };
+
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(String::from("A FEN needs 8 ranks."));
+    }
+
+    let mut squares: Vec<Vec<Square>> = Vec::new();
+    let mut promoted_squares: Vec<(u8, u8)> = Vec::new();
+    // FEN starts from the 8th rank
+    for (row, rank) in ranks.iter().rev().enumerate() {
+        let mut row_squares: Vec<Square> = Vec::new();
+        for letter in rank.chars() {
+            // a ~ after a piece means it was promoted
+            if letter == '~' && !row_squares.is_empty() {
+                promoted_squares.push((row as u8, row_squares.len() as u8 - 1));
+            } else if let Some(empty) = letter.to_digit(10) {
+                for _ in 0..empty {
+                    row_squares.push(Square {
+                        piece: Piece {
+                            piece_type: PieceType::None,
+                            color: Color::None,
+                        },
+                    });
+                }
+            } else {
+                match letter_to_piece(letter) {
+                    Some(piece) => row_squares.push(Square { piece }),
+                    None => return Err(format!("Unknown piece in FEN: {}", letter)),
+                }
+            }
+        }
+        if row_squares.len() != 8 {
+            return Err(format!("FEN rank doesn't have 8 squares: {}", rank));
+        }
+        squares.push(row_squares);
+    }
+
+    let current_turn = match fields[1] {
+        "w" => Color::White,
+        "b" => Color::Black,
+        _ => return Err(String::from("Side to move must be w or b.")),
+    };
+
+    let mut board = Board {
+        squares,
+        current_turn,
+        in_check: Color::None,
+        castling_rooks: Vec::new(),
+        promoted_squares,
+        drops: pocket.is_some(),
+        first_rank_double_steps: false,
+        white_pocket: Vec::new(),
+        black_pocket: Vec::new(),
+    };
+
+    // Crazyhouse pockets, like [Qp], go straight after the pieces
+    for letter in pocket.unwrap_or("").chars() {
+        match letter_to_piece(letter) {
+            Some(piece) if piece.piece_type != PieceType::King => {
+                board.pocket_mut(piece.color).push(piece.piece_type)
+            }
+            _ => return Err(format!("Unknown piece in FEN pocket: {}", letter)),
+        }
+    }
+
+    if fields[2] != "-" {
+        for letter in fields[2].chars() {
+            let (color, row) = if letter.is_ascii_uppercase() {
+                (Color::White, 0)
+            } else {
+                (Color::Black, 7)
+            };
+            let king_col = match board.castling_king_col(color) {
+                Some(king_col) => king_col,
+                None => {
+                    return Err(format!(
+                        "There is no king to castle with for castling right {} in the FEN.",
+                        letter
+                    ))
+                }
+            };
+
+            let is_own_rook = |col: u8| {
+                let piece = &board.squares[row as usize][col as usize].piece;
+                piece.piece_type == PieceType::Rook && piece.color == color
+            };
+
+            // K and Q mean the outermost rook on that side of the king
+            let rook_col = match letter.to_ascii_lowercase() {
+                'k' => (king_col + 1..8).rev().find(|&col| is_own_rook(col)),
+                'q' => (0..king_col).find(|&col| is_own_rook(col)),
+                'a'..='h' => {
+                    let col = letter.to_ascii_lowercase() as u8 - b'a';
+                    if is_own_rook(col) {
+                        Some(col)
+                    } else {
+                        None
+                    }
+                }
+                _ => return Err(format!("Unknown castling right in FEN: {}", letter)),
+            };
+
+            match rook_col {
+                Some(col) => board.castling_rooks.push((row, col)),
+                None => {
+                    return Err(format!(
+                        "There is no rook for castling right {} in the FEN.",
+                        letter
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(board)
+}
+
+fn letter_to_piece(letter: char) -> Option<Piece> {
+    let piece_type = PieceType::from_letter(letter)?;
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some(Piece { piece_type, color })
+}
+
+fn piece_to_letter(piece: &Piece) -> Option<char> {
+    let letter = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+        _ => return None,
+    };
+    if piece.color == Color::White {
+        Some(letter.to_ascii_uppercase())
+    } else {
+        Some(letter)
     }
 }
 
@@ -169,11 +791,87 @@ pub fn print_board(gameboard: &Board) {
                     PieceType::King => "k ",
                     _ => "# ",
                 },
-                Color::None => "#",
+                Color::None => "# ",
             };
             row_str.push_str(square_str);
         }
         println!("{:?}", row_str);
     }
-    println!("");
+    if gameboard.drops {
+        for color in [Color::White, Color::Black] {
+            let mut pocket_str = "".to_string();
+            for piece_type in gameboard.pocket(color) {
+                let piece = Piece {
+                    piece_type: piece_type.clone(),
+                    color,
+                };
+                if let Some(letter) = piece_to_letter(&piece) {
+                    pocket_str.push(letter);
+                    pocket_str.push(' ');
+                }
+            }
+            println!("{:?} pocket: {}", color, pocket_str);
+        }
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess960_518_is_the_standard_setup() {
+        let chess960 = create_chess960_board(518).unwrap();
+        let standard = create_board();
+        assert_eq!(chess960.to_fen(false), standard.to_fen(false));
+        assert_eq!(chess960.to_fen(true), standard.to_fen(true));
+    }
+
+    #[test]
+    fn chess960_first_and_last_positions() {
+        assert_eq!(
+            create_chess960_board(0).unwrap().to_fen(false),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert_eq!(
+            create_chess960_board(959).unwrap().to_fen(false),
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1"
+        );
+        assert!(create_chess960_board(960).is_err());
+    }
+
+    #[test]
+    fn x_fen_round_trips() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            // the g8 rook isn't the outermost one, so it has to be written by its file
+            "1r2k1rr/8/8/8/8/8/8/R3K2R b KQg - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert_eq!(create_board_from_fen(fen).unwrap().to_fen(false), fen);
+        }
+    }
+
+    #[test]
+    fn shredder_fen_round_trips() {
+        let fen = "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1";
+        let board = create_board_from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(true), fen);
+        assert_eq!(
+            board.to_fen(false),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+    }
+
+    #[test]
+    fn castling_checks_the_squares_after_the_rook_moves() {
+        // the b1 rook shields c1 from the a1 rook until it castles away to d1
+        let board = create_board_from_fen("k7/8/8/8/8/8/8/rR2K3 w B - 0 1").unwrap();
+        assert!(!board.can_castle((0, 4), (0, 1)));
+
+        let board = create_board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert!(board.can_castle((0, 4), (0, 7)));
+        assert!(board.can_castle((0, 4), (0, 0)));
+    }
 }
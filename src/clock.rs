@@ -0,0 +1,311 @@
+use crate::Color;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum ClockMode {
+    // the bonus is added after every move
+    Fischer,
+    // the clock runs straight away, then gives back the time used, up to the bonus
+    Bronstein,
+    // the clock waits for the bonus to run out before it starts
+    SimpleDelay,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stage {
+    // None means the stage lasts for the rest of the game
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerClock {
+    pub remaining: Duration,
+    pub moves: u32,
+    pub stage: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    pub stages: Vec<Stage>,
+    pub mode: ClockMode,
+    pub white: PlayerClock,
+    pub black: PlayerClock,
+    pub turn_started: Instant,
+}
+
+impl Clock {
+    pub fn new(stages: Vec<Stage>, mode: ClockMode) -> Clock {
+        let player_clock = PlayerClock {
+            remaining: stages[0].time,
+            moves: 0,
+            stage: 0,
+        };
+
+        Clock {
+            stages,
+            mode,
+            white: player_clock.clone(),
+            black: player_clock,
+            turn_started: Instant::now(),
+        }
+    }
+
+    pub fn start_turn(&mut self) {
+        self.turn_started = Instant::now();
+    }
+
+    // checks whether the player to move has run out of time during this turn
+    pub fn flagged(&self, color: Color) -> bool {
+        self.flagged_after(color, self.turn_started.elapsed())
+    }
+
+    fn flagged_after(&self, color: Color, elapsed: Duration) -> bool {
+        let player_clock = self.player_clock(color);
+        let bonus = self.stages[player_clock.stage].bonus;
+
+        match self.mode {
+            ClockMode::SimpleDelay => elapsed.saturating_sub(bonus) > player_clock.remaining,
+            ClockMode::Fischer | ClockMode::Bronstein => elapsed > player_clock.remaining,
+        }
+    }
+
+    // takes the time used off the player who just moved, and adds any bonus or next stage time
+    pub fn finish_turn(&mut self, color: Color) {
+        self.charge_turn(color, self.turn_started.elapsed());
+        self.start_turn();
+    }
+
+    // the clock arithmetic for a turn that took the given time
+    fn charge_turn(&mut self, color: Color, elapsed: Duration) {
+        let mode = self.mode;
+        let stages = self.stages.clone();
+        let player_clock = self.player_clock_mut(color);
+        let bonus = stages[player_clock.stage].bonus;
+
+        let used = match mode {
+            ClockMode::Fischer => elapsed,
+            ClockMode::Bronstein | ClockMode::SimpleDelay => elapsed.saturating_sub(bonus),
+        };
+        player_clock.remaining = player_clock.remaining.saturating_sub(used);
+
+        if mode == ClockMode::Fischer {
+            player_clock.remaining = player_clock.remaining.saturating_add(bonus);
+        }
+
+        player_clock.moves += 1;
+
+        // move to the next stage once this one's moves are made
+        // a last stage with a move count starts over, like 40/90 repeating every 40 moves
+        if let Some(stage_moves) = stages[player_clock.stage].moves {
+            if player_clock.moves == stage_moves {
+                if player_clock.stage + 1 < stages.len() {
+                    player_clock.stage += 1;
+                }
+                player_clock.remaining = player_clock
+                    .remaining
+                    .saturating_add(stages[player_clock.stage].time);
+                player_clock.moves = 0;
+            }
+        }
+    }
+
+    fn player_clock(&self, color: Color) -> &PlayerClock {
+        if color == Color::White {
+            &self.white
+        } else {
+            &self.black
+        }
+    }
+
+    fn player_clock_mut(&mut self, color: Color) -> &mut PlayerClock {
+        if color == Color::White {
+            &mut self.white
+        } else {
+            &mut self.black
+        }
+    }
+}
+
+// parses a time control like "5+3", "90" or "40/90+30:30+30"
+// each stage is [moves/]minutes[+seconds], with stages split by ':'
+pub fn parse_time_control(text: &str) -> Result<Vec<Stage>, String> {
+    let mut stages: Vec<Stage> = Vec::new();
+
+    for stage_text in text.split(':') {
+        let (moves, rest) = match stage_text.split_once('/') {
+            Some((moves, rest)) => match moves.parse::<u32>() {
+                Ok(moves) if moves > 0 => (Some(moves), rest),
+                _ => return Err(format!("Invalid move count in {}.", stage_text)),
+            },
+            None => (None, stage_text),
+        };
+
+        let (minutes, seconds) = match rest.split_once('+') {
+            Some((minutes, seconds)) => (minutes, seconds),
+            None => (rest, "0"),
+        };
+
+        let minutes = match minutes.parse::<u64>() {
+            Ok(minutes) => minutes,
+            Err(_err) => return Err(format!("Invalid minutes in {}.", stage_text)),
+        };
+        let seconds = match seconds.parse::<u64>() {
+            Ok(seconds) => seconds,
+            Err(_err) => return Err(format!("Invalid seconds in {}.", stage_text)),
+        };
+        let minutes_in_seconds = match minutes.checked_mul(60) {
+            Some(minutes_in_seconds) => minutes_in_seconds,
+            None => return Err(format!("Too many minutes in {}.", stage_text)),
+        };
+
+        stages.push(Stage {
+            moves,
+            time: Duration::from_secs(minutes_in_seconds),
+            bonus: Duration::from_secs(seconds),
+        });
+    }
+
+    if stages[0].time.is_zero() {
+        return Err(String::from(
+            "The first stage needs some time on the clock.",
+        ));
+    }
+    // every stage but the last needs a move count, otherwise the later ones are never reached
+    for stage in &stages[..stages.len() - 1] {
+        if stage.moves.is_none() {
+            return Err(String::from(
+                "Only the last stage can last for the rest of the game.",
+            ));
+        }
+    }
+
+    Ok(stages)
+}
+
+pub fn parse_clock_mode(text: &str) -> Option<ClockMode> {
+    match text {
+        "fischer" => Some(ClockMode::Fischer),
+        "bronstein" => Some(ClockMode::Bronstein),
+        "delay" => Some(ClockMode::SimpleDelay),
+        _ => None,
+    }
+}
+
+pub fn print_clock(clock: &Clock) {
+    println!(
+        "White {}  Black {}",
+        format_time(clock.white.remaining),
+        format_time(clock.black.remaining)
+    );
+    println!();
+}
+
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn parses_stages() {
+        let stages = parse_time_control("40/90+30:30+30").unwrap();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].moves, Some(40));
+        assert_eq!(stages[0].time, seconds(90 * 60));
+        assert_eq!(stages[0].bonus, seconds(30));
+        assert_eq!(stages[1].moves, None);
+        assert_eq!(stages[1].time, seconds(30 * 60));
+        assert_eq!(stages[1].bonus, seconds(30));
+
+        let stages = parse_time_control("5").unwrap();
+        assert_eq!(stages[0].time, seconds(5 * 60));
+        assert_eq!(stages[0].bonus, seconds(0));
+    }
+
+    #[test]
+    fn rejects_bad_time_controls() {
+        assert_eq!(
+            parse_time_control("1000000000000000000+0").unwrap_err(),
+            "Too many minutes in 1000000000000000000+0."
+        );
+        assert_eq!(
+            parse_time_control("30:40/90").unwrap_err(),
+            "Only the last stage can last for the rest of the game."
+        );
+        assert!(parse_time_control("0+5").is_err());
+        assert!(parse_time_control("0/5").is_err());
+        assert!(parse_time_control("5+x").is_err());
+    }
+
+    #[test]
+    fn fischer_adds_the_bonus_after_every_move() {
+        let mut clock = Clock::new(parse_time_control("1+5").unwrap(), ClockMode::Fischer);
+        clock.charge_turn(Color::White, seconds(10));
+        assert_eq!(clock.white.remaining, seconds(55));
+        clock.charge_turn(Color::White, seconds(2));
+        assert_eq!(clock.white.remaining, seconds(58));
+        assert_eq!(clock.black.remaining, seconds(60));
+    }
+
+    #[test]
+    fn bronstein_and_delay_only_charge_time_past_the_bonus() {
+        for mode in [ClockMode::Bronstein, ClockMode::SimpleDelay] {
+            let mut clock = Clock::new(parse_time_control("1+5").unwrap(), mode);
+            clock.charge_turn(Color::White, seconds(3));
+            assert_eq!(clock.white.remaining, seconds(60));
+            clock.charge_turn(Color::White, seconds(10));
+            assert_eq!(clock.white.remaining, seconds(55));
+        }
+    }
+
+    #[test]
+    fn only_simple_delay_waits_before_the_clock_runs() {
+        let clock = Clock::new(parse_time_control("1+5").unwrap(), ClockMode::SimpleDelay);
+        assert!(!clock.flagged_after(Color::White, seconds(64)));
+        assert!(clock.flagged_after(Color::White, seconds(66)));
+
+        for mode in [ClockMode::Fischer, ClockMode::Bronstein] {
+            let clock = Clock::new(parse_time_control("1+5").unwrap(), mode);
+            assert!(!clock.flagged_after(Color::White, seconds(59)));
+            assert!(clock.flagged_after(Color::White, seconds(61)));
+        }
+    }
+
+    #[test]
+    fn moves_on_to_the_next_stage() {
+        let mut clock = Clock::new(parse_time_control("2/10:5").unwrap(), ClockMode::Fischer);
+        clock.charge_turn(Color::White, seconds(60));
+        assert_eq!(clock.white.stage, 0);
+        clock.charge_turn(Color::White, seconds(60));
+        assert_eq!(clock.white.stage, 1);
+        assert_eq!(clock.white.moves, 0);
+        assert_eq!(clock.white.remaining, seconds(8 * 60 + 5 * 60));
+
+        // the last stage has no move count, so it never ends
+        for _ in 0..5 {
+            clock.charge_turn(Color::White, seconds(1));
+        }
+        assert_eq!(clock.white.stage, 1);
+        assert_eq!(clock.white.remaining, seconds(13 * 60 - 5));
+    }
+
+    #[test]
+    fn a_last_stage_with_a_move_count_repeats() {
+        let mut clock = Clock::new(parse_time_control("2/10").unwrap(), ClockMode::Fischer);
+        for _ in 0..2 {
+            clock.charge_turn(Color::White, seconds(60));
+        }
+        assert_eq!(clock.white.stage, 0);
+        assert_eq!(clock.white.moves, 0);
+        assert_eq!(clock.white.remaining, seconds(8 * 60 + 10 * 60));
+    }
+}
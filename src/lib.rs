@@ -7,7 +7,68 @@ pub enum Color {
     Black,
 }
 
-pub fn get_cli_input() -> Result<(u8, u8, u8, u8), String> {
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn from_notation(notation: &str) -> Option<GameResult> {
+        match notation {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+
+    // white's score: 1 for a win, 0.5 for a draw, 0 for a loss
+    pub fn white_score(&self) -> f64 {
+        match self {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+
+    pub fn notation(&self) -> &str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+}
+
+// player names are saved with the fields split by spaces, so they can't have any in them
+pub fn check_player_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!(
+            "Player names can't be empty or have spaces in them: \"{}\".",
+            name
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliMove {
+    // a piece moving between two (rank, file) squares, with what a pawn promotes to if it was given
+    Move {
+        from: (u8, u8),
+        to: (u8, u8),
+        promotion: Option<char>,
+    },
+    // a piece put onto the board from the pocket, like N@f3
+    Drop {
+        piece: char,
+        to: (u8, u8),
+    },
+}
+
+pub fn get_cli_input() -> Result<CliMove, String> {
     let mut user_input = "".to_string();
 
     io::stdin()
@@ -17,27 +78,63 @@ pub fn get_cli_input() -> Result<(u8, u8, u8, u8), String> {
 
     let input_square: Vec<&str> = trimmed_input.split(' ').collect();
 
-    if input_square.len() != 2 {
-        return Err(String::from("Needs 2 arguments. (Example: e2 e4)"));
+    if input_square.len() == 1 {
+        if let Some((piece, to_square)) = trimmed_input.split_once('@') {
+            return parse_drop(piece, to_square);
+        }
     }
 
-    let from_square = input_square[0];
-    let to_square = input_square[1];
-
-    if from_square.len() != 2 || to_square.len() != 2 {
-        return Err(String::from("Please enter a valid move. (Example: e2 e4)"));
+    if input_square.len() != 2 && input_square.len() != 3 {
+        return Err(String::from(
+            "Needs 2 arguments, and optionally a piece to promote to. (Example: e2 e4, or e7 e8 q)",
+        ));
     }
 
-    let from_file_result = letter_to_number(&from_square[0..1]);
-    let to_file_result = letter_to_number(&to_square[0..1]);
+    let from = parse_square(input_square[0])?;
+    let to = parse_square(input_square[1])?;
+
+    let promotion = match input_square.get(2) {
+        Some(piece) => {
+            let mut letters = piece.chars();
+            match (letters.next(), letters.next()) {
+                (Some(letter), None) => Some(letter.to_ascii_lowercase()),
+                _ => {
+                    return Err(String::from(
+                        "Promote to one piece letter. (Example: e7 e8 q)",
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
 
-    let from_file = match from_file_result {
-        Some(x) => x,
-        None => {
-            return Err(String::from("Invalid file."));
+    Ok(CliMove::Move {
+        from,
+        to,
+        promotion,
+    })
+}
+
+fn parse_drop(piece: &str, to_square: &str) -> Result<CliMove, String> {
+    let mut letters = piece.chars();
+    let piece = match (letters.next(), letters.next()) {
+        (Some(letter), None) if "pnbrq".contains(letter.to_ascii_lowercase()) => {
+            letter.to_ascii_lowercase()
         }
+        _ => return Err(String::from("Please enter a valid drop. (Example: N@f3)")),
     };
-    let to_file = match to_file_result {
+
+    let to = parse_square(to_square)?;
+    Ok(CliMove::Drop { piece, to })
+}
+
+// turns a square like e4 into its (rank, file), both counting from 0
+fn parse_square(square: &str) -> Result<(u8, u8), String> {
+    if square.len() != 2 || !square.is_ascii() {
+        return Err(String::from("Please enter a valid move. (Example: e2 e4)"));
+    }
+
+    let file = match letter_to_number(&square[0..1]) {
         Some(x) => x,
         None => {
             return Err(String::from("Invalid file."));
@@ -45,37 +142,25 @@ pub fn get_cli_input() -> Result<(u8, u8, u8, u8), String> {
     };
 
     // dont forget to do -1
-    let from_rank_result = &from_square[1..2].parse::<u8>();
-    let from_rank: u8;
-
-    match from_rank_result {
-        Ok(rank) => {
-            from_rank = rank - 1;
-        }
-        Err(_err) => {
-            return Err(String::from("Invalid rank."));
-        }
-    };
-
-    let to_rank_result = &to_square[1..2].parse::<u8>();
-    let to_rank: u8;
-
-    match to_rank_result {
-        Ok(rank) => {
-            to_rank = rank - 1;
+    let rank = match square[1..2].parse::<u8>() {
+        Ok(rank) if rank >= 1 => rank - 1,
+        Ok(_rank) => {
+            return Err(String::from(
+                "You cannot move from or to outside the board.",
+            ));
         }
         Err(_err) => {
             return Err(String::from("Invalid rank."));
         }
     };
 
-    if from_rank > 7 || to_rank > 7 {
+    if rank > 7 {
         return Err(String::from(
             "You cannot move from or to outside the board.",
         ));
     }
 
-    Ok((from_rank, from_file, to_rank, to_file))
+    Ok((rank, file))
 }
 
 fn letter_to_number(letter: &str) -> Option<u8> {
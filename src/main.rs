@@ -1,52 +1,364 @@
 use rust_chess::*;
+use std::env;
 
 mod board;
+mod clock;
 mod piece;
+mod rating;
+mod tournament;
+mod variant;
 
 use board::*;
+use clock::*;
 use piece::*;
+use rating::*;
+use tournament::*;
+use variant::*;
+
+const RATINGS_FILE: &str = "ratings.txt";
+const TOURNAMENT_FILE: &str = "tournament.txt";
 
 fn main() {
-    let mut gameboard = create_board();
-    let playing = true;
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("rate") | Some("leaderboard") | Some("tournament") => run_command(&args),
+        _ => play(&args),
+    };
+    if let Err(err) = result {
+        println!("{}", err);
+    }
+}
+
+fn play(args: &[String]) -> Result<(), String> {
+    let options = parse_game_options(args)?;
+    // games are only rated when both players are named
+    let players = match (options.white, options.black) {
+        (Some(white), Some(black)) => {
+            check_player_name(&white)?;
+            check_player_name(&black)?;
+            if white == black {
+                return Err(String::from("A player can't play against themselves."));
+            }
+            Some((white, black))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(String::from(
+                "--white and --black have to be given together.",
+            ))
+        }
+    };
+    let mut clock = options.clock;
+    let mut variant = options.variant;
+    let mut gameboard = match options.board {
+        Some(board) => board,
+        None => variant.create_board(),
+    };
+    gameboard.drops = variant.has_drops();
+    if !variant.has_castling() {
+        gameboard.castling_rooks.clear();
+    }
+    gameboard.first_rank_double_steps = variant.has_first_rank_double_steps();
 
+    println!("Playing {}.", variant.name());
     print_board(&gameboard);
+    if let Some(clock) = &mut clock {
+        print_clock(clock);
+        clock.start_turn();
+    }
 
-    while playing {
-        gameboard.putting_in_check();
+    let result = loop {
+        if variant.has_check() {
+            gameboard.putting_in_check();
+        }
         let cli_input_result = get_cli_input();
-        let positions: (u8, u8, u8, u8);
-        match cli_input_result {
-            Ok(move_pos) => positions = move_pos,
+
+        let mover = gameboard.current_turn;
+        if let Some(clock) = &clock {
+            if clock.flagged(mover) {
+                break print_flag_fall(&gameboard, mover, variant.as_ref());
+            }
+        }
+
+        let cli_move = match cli_input_result {
+            Ok(cli_move) => cli_move,
             Err(err) => {
                 println!("{}", err);
                 continue;
             }
-        }
-        let from_row = positions.0;
-        let from_col = positions.1;
-        let to_row = positions.2;
-        let to_col = positions.3;
-        println!("{:?}", positions);
+        };
 
-        let piece = &gameboard.squares[from_row as usize][from_col as usize]
-            .piece
-            .clone();
-        let new_gameboard =
-            piece.move_piece((from_row, from_col), (to_row, to_col), &mut gameboard);
+        match cli_move {
+            CliMove::Move {
+                from,
+                to,
+                promotion,
+            } => {
+                println!("{:?}", (from.0, from.1, to.0, to.1));
 
-        let updated_gameboard = new_gameboard;
-        gameboard = updated_gameboard;
+                let promotion = match promotion {
+                    Some(letter) => match PieceType::from_letter(letter) {
+                        Some(piece_type) if variant.can_promote_to(&piece_type) => piece_type,
+                        _ => {
+                            println!("Pawns can't promote to that in {}.", variant.name());
+                            continue;
+                        }
+                    },
+                    None => PieceType::Queen,
+                };
+
+                if let Err(err) = variant.check_move(&gameboard, from, to) {
+                    println!("{}", err);
+                    continue;
+                }
+
+                let piece = &gameboard.squares[from.0 as usize][from.1 as usize]
+                    .piece
+                    .clone();
+                let target = gameboard.squares[to.0 as usize][to.1 as usize]
+                    .piece
+                    .clone();
+                gameboard = piece.move_piece(from, to, promotion, &mut gameboard);
+
+                let captured = target.piece_type != PieceType::None && target.color != mover;
+                if captured && gameboard.current_turn != mover {
+                    variant.after_capture(&mut gameboard, to);
+                }
+            }
+            CliMove::Drop { piece, to } => {
+                let piece = Piece {
+                    piece_type: PieceType::from_letter(piece).unwrap_or(PieceType::None),
+                    color: mover,
+                };
+
+                if let Err(err) = variant.check_drop(&gameboard, &piece, to) {
+                    println!("{}", err);
+                    continue;
+                }
+
+                gameboard = piece.drop_piece(to, &mut gameboard);
+            }
+        }
+
+        // the turn only passes once a move was actually made
+        let moved = gameboard.current_turn != mover;
+        let result = if moved {
+            variant.game_over(&gameboard)
+        } else {
+            None
+        };
 
         print_board(&gameboard);
+        if let Some(status) = variant.status() {
+            println!("{}", status);
+        }
+        if let Some(clock) = &mut clock {
+            if moved {
+                clock.finish_turn(mover);
+            }
+            print_clock(clock);
+        }
+
+        if let Some(result) = result {
+            break result;
+        }
+    };
+
+    println!("Result: {}", result.notation());
+    if let Some((white, black)) = players {
+        let mut registry = Registry::load(RATINGS_FILE)?;
+        registry.record(&white, &black, result)?;
+        print_leaderboard(&registry);
     }
+    Ok(())
 }
 
-fn square_is_empty(piece: &Piece, row: u8, col: u8, gameboard: &Board) -> bool {
-    let to_square = &gameboard.squares[row as usize][col as usize];
-    to_square.piece.piece_type == PieceType::None || to_square.piece.color != piece.color
+struct GameOptions {
+    // None means the variant's own starting position
+    board: Option<Board>,
+    clock: Option<Clock>,
+    variant: Box<dyn Variant>,
+    // player names, for rating the game once it's over
+    white: Option<String>,
+    black: Option<String>,
 }
 
-fn print_type_of<T>(_: &T) {
-    println!("{}", std::any::type_name::<T>())
+fn parse_game_options(args: &[String]) -> Result<GameOptions, String> {
+    let mut board = None;
+    let mut stages = None;
+    let mut mode = ClockMode::Fischer;
+    let mut variant: Box<dyn Variant> = Box::new(Standard);
+    let mut white = None;
+    let mut black = None;
+
+    let mut index = 0;
+    while index < args.len() {
+        let value = match args.get(index + 1) {
+            Some(value) => value,
+            None => return Err(format!("{} needs a value.", args[index])),
+        };
+
+        match args[index].as_str() {
+            "--clock" => stages = Some(parse_time_control(value)?),
+            "--clock-mode" => {
+                mode = match parse_clock_mode(value) {
+                    Some(mode) => mode,
+                    None => {
+                        return Err(String::from(
+                            "Clock mode must be fischer, bronstein or delay.",
+                        ))
+                    }
+                }
+            }
+            "--chess960" => {
+                let position = if value == "random" {
+                    random_chess960_index()
+                } else {
+                    match value.parse::<u16>() {
+                        Ok(position) => position,
+                        Err(_err) => {
+                            return Err(String::from(
+                                "Chess960 position must be a number from 0 to 959, or random.",
+                            ))
+                        }
+                    }
+                };
+                let chess960_board = create_chess960_board(position)?;
+                println!(
+                    "Chess960 position {}: {}",
+                    position,
+                    chess960_board.to_fen(false)
+                );
+                board = Some(chess960_board);
+            }
+            "--fen" => board = Some(create_board_from_fen(value)?),
+            "--variant" => {
+                variant =
+                    match variant_from_name(value) {
+                        Some(variant) => variant,
+                        None => return Err(String::from(
+                            "Variant must be standard, threecheck, kingofthehill, racingkings, crazyhouse, atomic, antichess or horde.",
+                        )),
+                    }
+            }
+            "--white" => white = Some(value.to_string()),
+            "--black" => black = Some(value.to_string()),
+            option => return Err(format!("Unknown option: {}", option)),
+        }
+        index += 2;
+    }
+
+    Ok(GameOptions {
+        board,
+        clock: stages.map(|stages| Clock::new(stages, mode)),
+        variant,
+        white,
+        black,
+    })
+}
+
+fn print_flag_fall(gameboard: &Board, flagged: Color, variant: &dyn Variant) -> GameResult {
+    let opponent = if flagged == Color::White {
+        Color::Black
+    } else {
+        Color::White
+    };
+
+    println!("{:?} ran out of time.", flagged);
+    // running out of time is only a loss if the opponent could still win
+    if variant.can_still_win(gameboard, opponent) {
+        if opponent == Color::White {
+            GameResult::WhiteWins
+        } else {
+            GameResult::BlackWins
+        }
+    } else {
+        println!("{:?} can't win anymore, so the game is drawn.", opponent);
+        GameResult::Draw
+    }
+}
+
+fn run_command(args: &[String]) -> Result<(), String> {
+    match args[0].as_str() {
+        "rate" => {
+            if args.len() != 4 {
+                return Err(String::from(
+                    "Usage: rate <white> <black> <1-0|0-1|1/2-1/2>",
+                ));
+            }
+            let result = match GameResult::from_notation(&args[3]) {
+                Some(result) => result,
+                None => return Err(String::from("Result must be 1-0, 0-1 or 1/2-1/2.")),
+            };
+
+            let mut registry = Registry::load(RATINGS_FILE)?;
+            registry.record(&args[1], &args[2], result)?;
+            print_leaderboard(&registry);
+            Ok(())
+        }
+        "leaderboard" => {
+            let registry = Registry::load(RATINGS_FILE)?;
+            print_leaderboard(&registry);
+            Ok(())
+        }
+        "tournament" => run_tournament_command(&args[1..]),
+        command => Err(format!("Unknown command: {}", command)),
+    }
+}
+
+fn run_tournament_command(args: &[String]) -> Result<(), String> {
+    let usage = String::from(
+        "Usage: tournament new <roundrobin|swiss <rounds>> <players...>, tournament result <white> <black> <1-0|0-1|1/2-1/2>, tournament pairings, tournament standings or tournament pgn <file>",
+    );
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("new") => {
+            let (format, players) = match args.get(1).map(|arg| arg.as_str()) {
+                Some("roundrobin") => (Format::RoundRobin, &args[2..]),
+                Some("swiss") => match args.get(2).map(|rounds| rounds.parse::<u32>()) {
+                    Some(Ok(rounds)) => (Format::Swiss(rounds), &args[3..]),
+                    _ => return Err(String::from("Swiss needs a number of rounds.")),
+                },
+                _ => return Err(usage),
+            };
+
+            let tournament = Tournament::create(TOURNAMENT_FILE, format, players)?;
+            print_round(&tournament);
+            Ok(())
+        }
+        Some("result") => {
+            if args.len() != 4 {
+                return Err(usage);
+            }
+            let result = match GameResult::from_notation(&args[3]) {
+                Some(result) => result,
+                None => return Err(String::from("Result must be 1-0, 0-1 or 1/2-1/2.")),
+            };
+
+            let mut tournament = Tournament::load(TOURNAMENT_FILE)?;
+            tournament.record(&args[1], &args[2], result)?;
+            print_round(&tournament);
+            Ok(())
+        }
+        Some("pairings") => {
+            print_round(&Tournament::load(TOURNAMENT_FILE)?);
+            Ok(())
+        }
+        Some("standings") => {
+            print_standings(&Tournament::load(TOURNAMENT_FILE)?);
+            Ok(())
+        }
+        Some("pgn") => {
+            let path = match args.get(1) {
+                Some(path) => path,
+                None => return Err(usage),
+            };
+            let tournament = Tournament::load(TOURNAMENT_FILE)?;
+            tournament.save_pgn(path)?;
+            println!("Saved {} games to {}.", tournament.games.len(), path);
+            Ok(())
+        }
+        _ => Err(usage),
+    }
 }
@@ -13,6 +13,20 @@ pub enum PieceType {
     King,
 }
 
+impl PieceType {
+    pub fn from_letter(letter: char) -> Option<PieceType> {
+        match letter.to_ascii_lowercase() {
+            'p' => Some(PieceType::Pawn),
+            'n' => Some(PieceType::Knight),
+            'b' => Some(PieceType::Bishop),
+            'r' => Some(PieceType::Rook),
+            'q' => Some(PieceType::Queen),
+            'k' => Some(PieceType::King),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Piece {
     pub piece_type: PieceType,
@@ -32,183 +46,177 @@ impl Piece {
         }
     }
 
-    pub fn can_move(&self, from: (u8, u8), to: (u8, u8), gameboard: &mut Board) -> bool {
+    pub fn can_move(&self, from: (u8, u8), to: (u8, u8), gameboard: &Board) -> bool {
         // implementation of can_move method for each type of piece
         let (from_row, from_col) = from;
         let (to_row, to_col) = to;
 
-        let from_square = &gameboard.squares[from_row as usize][from_col as usize];
+        // castling moves the king onto its own rook, so it has to be checked before that is ruled out
+        if self.piece_type == PieceType::King && gameboard.current_turn == self.color {
+            if let Some(rook) = gameboard.castling_rook(from, to) {
+                return gameboard.can_castle(from, rook);
+            }
+        }
+
         let to_square = &gameboard.squares[to_row as usize][to_col as usize];
 
         /* isn't taking own piece */
         if to_square.piece.color != self.color || to_square.piece.piece_type == PieceType::None {
-            if gameboard.in_check != from_square.piece.color {
-                // is turn
-                if gameboard.current_turn == self.color {
-                    if to_col <= 7 && to_row <= 7 {
-                        // possible col
-                        match self.piece_type {
-                            PieceType::Pawn => {
-                                if self.color == Color::White {
-                                    return (from_col == to_col // pawn is on same column 
-                          && (to_row == from_row + 1 || // AND pawn is moving one square
-                          (from_row == 1 && to_row == 3))) // OR pawn is on starting square, moving 2
-                          ||
-                          (from_col == to_col-1 || from_col == to_col+1) &&  // pawn moving to dif column AND
-                          (to_row == from_row + 1 && to_square.piece.piece_type != PieceType::None && to_square.piece.color != self.color);
-
-                                // enemy piece is takeable
-                                } else {
-                                    return (from_col == to_col // pawn is on same column 
-                          && (to_row == from_row - 1 || // AND pawn is moving one square
-                          (from_row == 6 && to_row == 4))) // OR pawn is on starting square, moving 2
-                          ||
-                          (from_col == to_col-1 || from_col == to_col+1) &&  // pawn moving to different column AND
-                          (to_row == from_row - 1 && to_square.piece.piece_type != PieceType::None && to_square.piece.color != self.color);
-                                    // piece one row ahead, and one column to side
-                                }
-                            }
-                            PieceType::Knight => {
-                                let row_diff = to_row.abs_diff(from_row);
-                                let col_diff = to_col.abs_diff(from_col);
+            // is turn
+            if gameboard.current_turn == self.color {
+                if to_col <= 7 && to_row <= 7 {
+                    // possible col
+                    match self.piece_type {
+                        PieceType::Pawn => {
+                            if self.color == Color::White {
+                                (from_col == to_col // pawn is on same column 
+                      && to_square.piece.piece_type == PieceType::None // AND the square ahead is empty
+                      && (to_row == from_row + 1 || // AND pawn is moving one square
+                      ((from_row == 1 || (from_row == 0 && gameboard.first_rank_double_steps)) && to_row == from_row + 2 && gameboard.squares[from_row as usize + 1][from_col as usize].piece.piece_type == PieceType::None))) // OR pawn is on starting square (or the first rank, in Horde), moving 2 over an empty square
+                      ||
+                      to_col.abs_diff(from_col) == 1 &&  // pawn moving to dif column AND
+                      (to_row == from_row + 1 && to_square.piece.piece_type != PieceType::None && to_square.piece.color != self.color)
 
-                                // row change must be 1 and col change 2, or vice versa
-                                (row_diff == 1 && col_diff == 2) || (row_diff == 2 && col_diff == 1)
+                            // enemy piece is takeable
+                            } else {
+                                (from_col == to_col // pawn is on same column 
+                      && to_square.piece.piece_type == PieceType::None // AND the square ahead is empty
+                      && (to_row + 1 == from_row || // AND pawn is moving one square
+                      (from_row == 6 && to_row == 4 && gameboard.squares[5][from_col as usize].piece.piece_type == PieceType::None))) // OR pawn is on starting square, moving 2 over an empty square
+                      ||
+                      to_col.abs_diff(from_col) == 1 &&  // pawn moving to different column AND
+                      (to_row + 1 == from_row && to_square.piece.piece_type != PieceType::None && to_square.piece.color != self.color)
+                                // piece one row ahead, and one column to side
                             }
-                            PieceType::Rook => match (from_row.cmp(&to_row), from_col.cmp(&to_col))
-                            {
-                                // if to_row < and to_col ==
-                                (Ordering::Less, Ordering::Equal) => {
-                                    // iterate through each row between
-                                    for row in from_row + 1..to_row {
-                                        // iterate through each square in between the rook and destination
-                                        let square =
-                                            &gameboard.squares[row as usize][from_col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            return false;
-                                        }
+                        }
+                        PieceType::Knight => {
+                            let row_diff = to_row.abs_diff(from_row);
+                            let col_diff = to_col.abs_diff(from_col);
+
+                            // row change must be 1 and col change 2, or vice versa
+                            (row_diff == 1 && col_diff == 2) || (row_diff == 2 && col_diff == 1)
+                        }
+                        PieceType::Rook => match (from_row.cmp(&to_row), from_col.cmp(&to_col)) {
+                            // if to_row < and to_col ==
+                            (Ordering::Less, Ordering::Equal) => {
+                                // iterate through each row between
+                                for row in from_row + 1..to_row {
+                                    // iterate through each square in between the rook and destination
+                                    let square =
+                                        &gameboard.squares[row as usize][from_col as usize];
+                                    if square.piece.piece_type != PieceType::None {
+                                        return false;
                                     }
-                                    true
                                 }
-                                // if to_row == and to_col <
-                                (Ordering::Equal, Ordering::Less) => {
-                                    // iterate through each col between
-                                    for col in from_col + 1..to_col {
-                                        // iterate through each square in between the rook and destination
-                                        let square =
-                                            &gameboard.squares[from_row as usize][col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            return false;
-                                        }
+                                true
+                            }
+                            // if to_row == and to_col <
+                            (Ordering::Equal, Ordering::Less) => {
+                                // iterate through each col between
+                                for col in from_col + 1..to_col {
+                                    // iterate through each square in between the rook and destination
+                                    let square =
+                                        &gameboard.squares[from_row as usize][col as usize];
+                                    if square.piece.piece_type != PieceType::None {
+                                        return false;
                                     }
-                                    true
                                 }
-                                // if to_row == and to_col >
-                                (Ordering::Equal, Ordering::Greater) => {
-                                    // iterate through each col between, but reversed order.
-                                    for col in (to_col + 1..from_col).rev() {
-                                        // iterate through each square in between the rook and destination
-                                        let square =
-                                            &gameboard.squares[from_row as usize][col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            return false;
-                                        }
+                                true
+                            }
+                            // if to_row == and to_col >
+                            (Ordering::Equal, Ordering::Greater) => {
+                                // iterate through each col between, but reversed order.
+                                for col in (to_col + 1..from_col).rev() {
+                                    // iterate through each square in between the rook and destination
+                                    let square =
+                                        &gameboard.squares[from_row as usize][col as usize];
+                                    if square.piece.piece_type != PieceType::None {
+                                        return false;
                                     }
-                                    true
                                 }
-                                // if to_row > and to_col ==
-                                (Ordering::Greater, Ordering::Equal) => {
-                                    // iterate through each row between, but reversed order.
-                                    for row in (to_row + 1..from_row).rev() {
-                                        // iterate through each square in between the rook and destination
-                                        let square =
-                                            &gameboard.squares[row as usize][from_col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            return false;
-                                        }
+                                true
+                            }
+                            // if to_row > and to_col ==
+                            (Ordering::Greater, Ordering::Equal) => {
+                                // iterate through each row between, but reversed order.
+                                for row in (to_row + 1..from_row).rev() {
+                                    // iterate through each square in between the rook and destination
+                                    let square =
+                                        &gameboard.squares[row as usize][from_col as usize];
+                                    if square.piece.piece_type != PieceType::None {
+                                        return false;
                                     }
-                                    true
                                 }
-                                _ => false,
-                            },
-                            PieceType::Bishop => {
-                                if from_row != to_row
-                                    && from_col != to_col
-                                    && to_row.abs_diff(from_row) == to_col.abs_diff(from_col)
-                                {
-                                    let row_step = (to_row as i8 - from_row as i8).signum(); // +1 for upward diagonal, -1 for downward diagonal
-                                    let col_step = (to_col as i8 - from_col as i8).signum(); // +1 for rightward diagonal, -1 for leftward diagonal
-
-                                    let mut row = from_row as i8 + row_step;
-                                    let mut col = from_col as i8 + col_step;
-
-                                    while row != to_row as i8 && col != to_col as i8 {
-                                        let square = &gameboard.squares[row as usize][col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            // square in between from and to squares are empty
-                                            return false;
-                                        }
-                                        row += row_step;
-                                        col += col_step;
-                                    }
+                                true
+                            }
+                            _ => false,
+                        },
+                        PieceType::Bishop
+                            if from_row != to_row
+                                && from_col != to_col
+                                && to_row.abs_diff(from_row) == to_col.abs_diff(from_col) =>
+                        {
+                            let row_step = (to_row as i8 - from_row as i8).signum(); // +1 for upward diagonal, -1 for downward diagonal
+                            let col_step = (to_col as i8 - from_col as i8).signum(); // +1 for rightward diagonal, -1 for leftward diagonal
+
+                            let mut row = from_row as i8 + row_step;
+                            let mut col = from_col as i8 + col_step;
 
-                                    true
-                                } else {
-                                    false
+                            while row != to_row as i8 && col != to_col as i8 {
+                                let square = &gameboard.squares[row as usize][col as usize];
+                                if square.piece.piece_type != PieceType::None {
+                                    // square in between from and to squares are empty
+                                    return false;
                                 }
+                                row += row_step;
+                                col += col_step;
                             }
-                            PieceType::Queen => {
-                                if from_row == to_row
-                                    || from_col == to_col
-                                    || (from_row as i8 - to_row as i8).abs()
-                                        == (from_col as i8 - to_col as i8).abs()
-                                {
-                                    let row_step = if from_row == to_row {
-                                        0
-                                    } else {
-                                        (to_row as i8 - from_row as i8).signum()
-                                    }; // +1 for upward diagonal, -1 for downward diagonal
-                                    let col_step = if from_col == to_col {
-                                        0
-                                    } else {
-                                        (to_col as i8 - from_col as i8).signum()
-                                    }; // +1 for rightward diagonal, -1 for leftward diagonal
-
-                                    let mut row = from_row as i8 + row_step;
-                                    let mut col = from_col as i8 + col_step;
-
-                                    while row != to_row as i8 || col != to_col as i8 {
-                                        let square = &gameboard.squares[row as usize][col as usize];
-                                        if square.piece.piece_type != PieceType::None {
-                                            return false;
-                                        }
-                                        row += row_step;
-                                        col += col_step;
-                                    }
 
-                                    true
-                                } else {
-                                    false
+                            true
+                        }
+                        PieceType::Queen
+                            if from_row == to_row
+                                || from_col == to_col
+                                || (from_row as i8 - to_row as i8).abs()
+                                    == (from_col as i8 - to_col as i8).abs() =>
+                        {
+                            let row_step = if from_row == to_row {
+                                0
+                            } else {
+                                (to_row as i8 - from_row as i8).signum()
+                            }; // +1 for upward diagonal, -1 for downward diagonal
+                            let col_step = if from_col == to_col {
+                                0
+                            } else {
+                                (to_col as i8 - from_col as i8).signum()
+                            }; // +1 for rightward diagonal, -1 for leftward diagonal
+
+                            let mut row = from_row as i8 + row_step;
+                            let mut col = from_col as i8 + col_step;
+
+                            while row != to_row as i8 || col != to_col as i8 {
+                                let square = &gameboard.squares[row as usize][col as usize];
+                                if square.piece.piece_type != PieceType::None {
+                                    return false;
                                 }
+                                row += row_step;
+                                col += col_step;
                             }
-                            PieceType::King => {
-                                return (to_row <= from_row + 1 && to_row >= from_row - 1) && (to_col <= from_col + 1 && to_col >= from_col - 1) && // can only move 1 square in any direction
-                      to_square.piece.piece_type == PieceType::None
-                                    || to_square.piece.color != self.color; // can only move to square if it is empty or has enemy piece on it
-                            }
-                            _ => false,
+
+                            true
                         }
-                    } else {
-                        false
+                        PieceType::King => {
+                            // can only move 1 square in any direction, taking own pieces is already ruled out above
+                            to_row.abs_diff(from_row) <= 1 && to_col.abs_diff(from_col) <= 1
+                        }
+                        _ => false,
                     }
-                // not this color's turn
                 } else {
-                    println!("It is not currently {:?}'s turn.", &self.color);
                     false
                 }
-            // IN CHECK, ADD STUFF LATER
+            // not this color's turn
             } else {
-                println!("In check.");
+                println!("It is not currently {:?}'s turn.", &self.color);
                 false
             }
         // trying to take own piece
@@ -217,11 +225,70 @@ impl Piece {
             false
         }
     }
-    pub fn move_piece(&self, from: (u8, u8), to: (u8, u8), gameboard: &mut Board) -> Board {
+    // promotion is what a pawn turns into if it reaches the last rank
+    pub fn move_piece(
+        &self,
+        from: (u8, u8),
+        to: (u8, u8),
+        promotion: PieceType,
+        gameboard: &mut Board,
+    ) -> Board {
         let (from_row, from_col) = from;
         let (to_row, to_col) = to;
 
+        if self.piece_type == PieceType::King && gameboard.current_turn == self.color {
+            if let Some(rook) = gameboard.castling_rook(from, to) {
+                if gameboard.can_castle(from, rook) {
+                    return self.castle(from, rook, gameboard);
+                }
+                println!("Can not castle there.");
+                return gameboard.clone();
+            }
+        }
+
         if self.can_move(from, to, gameboard) {
+            // a king or rook that moves, or a rook that gets taken, can't castle anymore
+            gameboard
+                .castling_rooks
+                .retain(|&rook| rook != from && rook != to);
+            if self.piece_type == PieceType::King {
+                gameboard
+                    .castling_rooks
+                    .retain(|&(rook_row, _)| rook_row != from_row);
+            }
+
+            let captured = gameboard.squares[to_row as usize][to_col as usize]
+                .piece
+                .clone();
+            if gameboard.drops
+                && captured.piece_type != PieceType::None
+                && captured.color != self.color
+            {
+                // promoted pieces go back to being pawns once they're taken
+                let pocket_piece = if gameboard.promoted_squares.contains(&to) {
+                    PieceType::Pawn
+                } else {
+                    captured.piece_type
+                };
+                gameboard.pocket_mut(self.color).push(pocket_piece);
+            }
+
+            // keep track of where promoted pieces are
+            gameboard.promoted_squares.retain(|&square| square != to);
+            for square in gameboard.promoted_squares.iter_mut() {
+                if *square == from {
+                    *square = to;
+                }
+            }
+
+            let last_row = if self.color == Color::White { 7 } else { 0 };
+            let piece_type = if self.piece_type == PieceType::Pawn && to_row == last_row {
+                gameboard.promoted_squares.push(to);
+                promotion
+            } else {
+                self.piece_type.clone()
+            };
+
             gameboard.squares[from_row as usize][from_col as usize] = Square {
                 piece: Piece {
                     piece_type: PieceType::None,
@@ -230,7 +297,7 @@ impl Piece {
             };
             gameboard.squares[to_row as usize][to_col as usize] = Square {
                 piece: Piece {
-                    piece_type: self.piece_type.clone(),
+                    piece_type,
                     color: self.color,
                 },
             };
@@ -246,4 +313,71 @@ impl Piece {
             gameboard.clone()
         }
     }
+    fn castle(&self, king: (u8, u8), rook: (u8, u8), gameboard: &mut Board) -> Board {
+        gameboard.squares = gameboard.with_castle(king, rook).squares;
+        gameboard
+            .castling_rooks
+            .retain(|&(rook_row, _)| rook_row != king.0);
+
+        if gameboard.current_turn == Color::White {
+            gameboard.current_turn = Color::Black;
+        } else {
+            gameboard.current_turn = Color::White;
+        }
+
+        gameboard.clone()
+    }
+    pub fn can_drop(&self, to: (u8, u8), gameboard: &Board) -> bool {
+        let (to_row, to_col) = to;
+
+        if !gameboard.drops {
+            println!("Pieces can only be dropped in Crazyhouse.");
+            return false;
+        }
+        if gameboard.current_turn != self.color {
+            println!("It is not currently {:?}'s turn.", &self.color);
+            return false;
+        }
+        if !gameboard.pocket(self.color).contains(&self.piece_type) {
+            println!("There is no {:?} in your pocket.", self.piece_type);
+            return false;
+        }
+        if gameboard.squares[to_row as usize][to_col as usize]
+            .piece
+            .piece_type
+            != PieceType::None
+        {
+            println!("Pieces can only be dropped on empty squares.");
+            return false;
+        }
+        // pawns can't be dropped on the first or last rank
+        if self.piece_type == PieceType::Pawn && (to_row == 0 || to_row == 7) {
+            println!("Pawns can't be dropped on the first or last rank.");
+            return false;
+        }
+        true
+    }
+    pub fn drop_piece(&self, to: (u8, u8), gameboard: &mut Board) -> Board {
+        let (to_row, to_col) = to;
+
+        if self.can_drop(to, gameboard) {
+            let pocket = gameboard.pocket_mut(self.color);
+            if let Some(index) = pocket
+                .iter()
+                .position(|piece_type| piece_type == &self.piece_type)
+            {
+                pocket.remove(index);
+            }
+
+            gameboard.squares[to_row as usize][to_col as usize] = Square {
+                piece: self.clone(),
+            };
+            if gameboard.current_turn == Color::White {
+                gameboard.current_turn = Color::Black;
+            } else {
+                gameboard.current_turn = Color::White;
+            }
+        }
+        gameboard.clone()
+    }
 }
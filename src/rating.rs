@@ -0,0 +1,260 @@
+use crate::{check_player_name, GameResult};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+const STARTING_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub name: String,
+    pub rating: f64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Registry {
+    pub players: Vec<Player>,
+    pub path: String,
+}
+
+impl Registry {
+    // the file only stores finished games, ratings are worked out by replaying them
+    pub fn load(path: &str) -> Result<Registry, String> {
+        let mut registry = Registry {
+            players: Vec::new(),
+            path: path.to_string(),
+        };
+
+        let history = match fs::read_to_string(path) {
+            Ok(history) => history,
+            // no games played yet
+            Err(_err) => return Ok(registry),
+        };
+
+        for (line_number, line) in history.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(format!("Bad game on line {} of {}.", line_number + 1, path));
+            }
+
+            let result = match GameResult::from_notation(fields[2]) {
+                Some(result) => result,
+                None => {
+                    return Err(format!(
+                        "Bad result on line {} of {}.",
+                        line_number + 1,
+                        path
+                    ));
+                }
+            };
+
+            registry.apply_result(fields[0], fields[1], result);
+        }
+
+        Ok(registry)
+    }
+
+    pub fn record(&mut self, white: &str, black: &str, result: GameResult) -> Result<(), String> {
+        check_player_name(white)?;
+        check_player_name(black)?;
+        if white == black {
+            return Err(String::from("A player can't play against themselves."));
+        }
+
+        let mut file = match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            Ok(file) => file,
+            Err(_err) => return Err(format!("Could not open {}.", self.path)),
+        };
+
+        if writeln!(file, "{} {} {}", white, black, result.notation()).is_err() {
+            return Err(format!("Could not write to {}.", self.path));
+        }
+
+        self.apply_result(white, black, result);
+        Ok(())
+    }
+
+    fn apply_result(&mut self, white: &str, black: &str, result: GameResult) {
+        let white_index = self.player_index(white);
+        let black_index = self.player_index(black);
+
+        let white_score = result.white_score();
+
+        let white_rating = self.players[white_index].rating;
+        let black_rating = self.players[black_index].rating;
+        let white_expected = 1.0 / (1.0 + 10f64.powf((black_rating - white_rating) / 400.0));
+
+        let change = K_FACTOR * (white_score - white_expected);
+        self.players[white_index].rating += change;
+        self.players[black_index].rating -= change;
+
+        match result {
+            GameResult::WhiteWins => {
+                self.players[white_index].wins += 1;
+                self.players[black_index].losses += 1;
+            }
+            GameResult::BlackWins => {
+                self.players[white_index].losses += 1;
+                self.players[black_index].wins += 1;
+            }
+            GameResult::Draw => {
+                self.players[white_index].draws += 1;
+                self.players[black_index].draws += 1;
+            }
+        }
+    }
+
+    // finds a player by name, adding them if they haven't played before
+    fn player_index(&mut self, name: &str) -> usize {
+        match self.players.iter().position(|player| player.name == name) {
+            Some(index) => index,
+            None => {
+                self.players.push(Player {
+                    name: name.to_string(),
+                    rating: STARTING_RATING,
+                    wins: 0,
+                    draws: 0,
+                    losses: 0,
+                });
+                self.players.len() - 1
+            }
+        }
+    }
+}
+
+pub fn print_leaderboard(registry: &Registry) {
+    if registry.players.is_empty() {
+        println!("No games have been rated yet.");
+        return;
+    }
+
+    let mut players = registry.players.clone();
+    // highest rating first
+    players.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+
+    println!(
+        "{:<4} {:<20} {:>6} {:>5} {:>5} {:>5}",
+        "#", "Player", "Elo", "W", "D", "L"
+    );
+    for (rank, player) in players.iter().enumerate() {
+        println!(
+            "{:<4} {:<20} {:>6.0} {:>5} {:>5} {:>5}",
+            rank + 1,
+            player.name,
+            player.rating,
+            player.wins,
+            player.draws,
+            player.losses
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a file in the temp directory that no other test uses
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "rust-chess-ratings-{}-{}.txt",
+            name,
+            std::process::id()
+        ));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn player<'a>(registry: &'a Registry, name: &str) -> &'a Player {
+        registry
+            .players
+            .iter()
+            .find(|player| player.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn elo_moves_by_half_the_k_factor_between_equals() {
+        let mut registry = Registry {
+            players: Vec::new(),
+            path: String::new(),
+        };
+        registry.apply_result("alice", "bob", GameResult::WhiteWins);
+        assert_eq!(player(&registry, "alice").rating, 1516.0);
+        assert_eq!(player(&registry, "bob").rating, 1484.0);
+
+        // the favourite only gains a little from beating the underdog, and loses more from drawing
+        registry.apply_result("alice", "bob", GameResult::WhiteWins);
+        let gain = player(&registry, "alice").rating - 1516.0;
+        assert!(gain > 0.0 && gain < 16.0);
+        let before = player(&registry, "alice").rating;
+        registry.apply_result("bob", "alice", GameResult::Draw);
+        assert!(player(&registry, "alice").rating < before);
+        // points only move between the two players
+        assert_eq!(
+            player(&registry, "alice").rating + player(&registry, "bob").rating,
+            3000.0
+        );
+    }
+
+    #[test]
+    fn load_replays_every_game() {
+        let path = temp_path("load");
+        fs::write(&path, "alice bob 1-0\nbob carol 1/2-1/2\ncarol alice 0-1\n").unwrap();
+
+        let registry = Registry::load(&path).unwrap();
+        let alice = player(&registry, "alice");
+        assert_eq!((alice.wins, alice.draws, alice.losses), (2, 0, 0));
+        let bob = player(&registry, "bob");
+        assert_eq!((bob.wins, bob.draws, bob.losses), (0, 1, 1));
+
+        // replaying gives the same ratings as applying the games one by one
+        let mut replayed = Registry {
+            players: Vec::new(),
+            path: String::new(),
+        };
+        replayed.apply_result("alice", "bob", GameResult::WhiteWins);
+        replayed.apply_result("bob", "carol", GameResult::Draw);
+        replayed.apply_result("carol", "alice", GameResult::BlackWins);
+        for name in ["alice", "bob", "carol"] {
+            assert_eq!(
+                player(&registry, name).rating,
+                player(&replayed, name).rating
+            );
+        }
+
+        fs::write(&path, "alice bob\n").unwrap();
+        assert!(Registry::load(&path).is_err());
+        fs::write(&path, "alice bob 2-0\n").unwrap();
+        assert!(Registry::load(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn record_saves_games_for_the_next_load() {
+        let path = temp_path("record");
+        let _ = fs::remove_file(&path);
+
+        let mut registry = Registry::load(&path).unwrap();
+        registry
+            .record("alice", "bob", GameResult::BlackWins)
+            .unwrap();
+        assert!(registry
+            .record("Bob Smith", "alice", GameResult::WhiteWins)
+            .is_err());
+        assert!(registry.record("", "alice", GameResult::WhiteWins).is_err());
+        assert!(registry.record("alice", "alice", GameResult::Draw).is_err());
+
+        let loaded = Registry::load(&path).unwrap();
+        assert_eq!(loaded.players.len(), 2);
+        assert_eq!(player(&loaded, "bob").wins, 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
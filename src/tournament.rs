@@ -0,0 +1,668 @@
+use crate::{check_player_name, GameResult};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub enum Format {
+    // everyone plays everyone once
+    RoundRobin,
+    // a set number of rounds, with players on the same score paired against each other
+    Swiss(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentGame {
+    pub round: u32,
+    pub white: String,
+    pub black: String,
+    pub result: GameResult,
+}
+
+// no black player means white has the bye that round
+#[derive(Debug, Clone)]
+pub struct Pairing {
+    pub white: String,
+    pub black: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub points: f64,
+    pub buchholz: f64,
+    pub sonneborn_berger: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub format: Format,
+    // in seeding order
+    pub players: Vec<String>,
+    pub games: Vec<TournamentGame>,
+    pub path: String,
+}
+
+impl Tournament {
+    // the file holds the format, the players and the results, pairings are worked out from those
+    pub fn create(path: &str, format: Format, players: &[String]) -> Result<Tournament, String> {
+        if Path::new(path).exists() {
+            return Err(format!(
+                "A tournament is already being played in {}. Delete it to start a new one.",
+                path
+            ));
+        }
+        check_entries(format, players)?;
+
+        let format_line = match format {
+            Format::RoundRobin => String::from("roundrobin"),
+            Format::Swiss(rounds) => format!("swiss {}", rounds),
+        };
+        if fs::write(path, format!("{}\n{}\n", format_line, players.join(" "))).is_err() {
+            return Err(format!("Could not write to {}.", path));
+        }
+
+        Ok(Tournament {
+            format,
+            players: players.to_vec(),
+            games: Vec::new(),
+            path: path.to_string(),
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Tournament, String> {
+        let history = match fs::read_to_string(path) {
+            Ok(history) => history,
+            Err(_err) => {
+                return Err(String::from(
+                    "No tournament has been started. Start one with tournament new.",
+                ))
+            }
+        };
+        let mut lines = history.lines();
+
+        let format_fields: Vec<&str> = lines.next().unwrap_or("").split_whitespace().collect();
+        let format = match format_fields[..] {
+            ["roundrobin"] => Format::RoundRobin,
+            ["swiss", rounds] => match rounds.parse::<u32>() {
+                Ok(rounds) => Format::Swiss(rounds),
+                Err(_err) => return Err(format!("Bad format on line 1 of {}.", path)),
+            },
+            _ => return Err(format!("Bad format on line 1 of {}.", path)),
+        };
+        let players: Vec<String> = lines
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|player| player.to_string())
+            .collect();
+        // the file could have been edited by hand, and the pairings rely on these
+        if let Err(err) = check_entries(format, &players) {
+            return Err(format!("Bad tournament in {}: {}", path, err));
+        }
+
+        let mut games: Vec<TournamentGame> = Vec::new();
+        for (line_number, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let game = match fields[..] {
+                [round, white, black, result] => {
+                    match (round.parse::<u32>(), GameResult::from_notation(result)) {
+                        (Ok(round), Some(result)) => Some(TournamentGame {
+                            round,
+                            white: white.to_string(),
+                            black: black.to_string(),
+                            result,
+                        }),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match game {
+                Some(game) => games.push(game),
+                None => return Err(format!("Bad game on line {} of {}.", line_number + 3, path)),
+            }
+        }
+
+        Ok(Tournament {
+            format,
+            players,
+            games,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn round_count(&self) -> u32 {
+        match self.format {
+            // with an odd number of players, everyone sits out once
+            Format::RoundRobin if self.players.len().is_multiple_of(2) => {
+                self.players.len() as u32 - 1
+            }
+            Format::RoundRobin => self.players.len() as u32,
+            Format::Swiss(rounds) => rounds,
+        }
+    }
+
+    // the pairings of every round up to the first one that hasn't finished,
+    // since later Swiss rounds depend on its results
+    pub fn pairings(&self) -> Vec<Vec<Pairing>> {
+        let mut rounds: Vec<Vec<Pairing>> = Vec::new();
+        for round in 1..=self.round_count() {
+            let pairings = match self.format {
+                Format::RoundRobin => self.round_robin_pairings(round),
+                Format::Swiss(_) => self.swiss_pairings(&rounds),
+            };
+            let finished = pairings.iter().all(|pairing| match &pairing.black {
+                Some(black) => self.game(round, &pairing.white, black).is_some(),
+                None => true,
+            });
+            rounds.push(pairings);
+            if !finished {
+                break;
+            }
+        }
+        rounds
+    }
+
+    // the first round with games still to play, or None once the tournament is over
+    pub fn current_round(&self) -> Option<u32> {
+        let rounds = self.pairings();
+        let round = rounds.len() as u32;
+        let finished = rounds[round as usize - 1]
+            .iter()
+            .all(|pairing| match &pairing.black {
+                Some(black) => self.game(round, &pairing.white, black).is_some(),
+                None => true,
+            });
+
+        if finished && round == self.round_count() {
+            None
+        } else {
+            Some(round)
+        }
+    }
+
+    pub fn game(&self, round: u32, white: &str, black: &str) -> Option<&TournamentGame> {
+        self.games
+            .iter()
+            .find(|game| game.round == round && game.white == white && game.black == black)
+    }
+
+    // results can only be given for games paired in the current round
+    pub fn record(&mut self, white: &str, black: &str, result: GameResult) -> Result<(), String> {
+        let round = match self.current_round() {
+            Some(round) => round,
+            None => return Err(String::from("The tournament is over.")),
+        };
+        let paired = self.pairings()[round as usize - 1]
+            .iter()
+            .any(|pairing| pairing.white == white && pairing.black.as_deref() == Some(black));
+        if !paired {
+            return Err(format!(
+                "{} (white) against {} (black) isn't a game in round {}.",
+                white, black, round
+            ));
+        }
+        if self.game(round, white, black).is_some() {
+            return Err(String::from("That game already has a result."));
+        }
+
+        let mut file = match OpenOptions::new().append(true).open(&self.path) {
+            Ok(file) => file,
+            Err(_err) => return Err(format!("Could not open {}.", self.path)),
+        };
+        if writeln!(file, "{} {} {} {}", round, white, black, result.notation()).is_err() {
+            return Err(format!("Could not write to {}.", self.path));
+        }
+
+        self.games.push(TournamentGame {
+            round,
+            white: white.to_string(),
+            black: black.to_string(),
+            result,
+        });
+        Ok(())
+    }
+
+    // every game as PGN, with just the result as the movetext since the moves aren't recorded
+    pub fn to_pgn(&self) -> String {
+        let event = match self.format {
+            Format::RoundRobin => "Round-robin tournament",
+            Format::Swiss(_) => "Swiss tournament",
+        };
+
+        let mut pgn = "".to_string();
+        for game in &self.games {
+            pgn.push_str(&format!("[Event \"{}\"]\n", event));
+            pgn.push_str("[Site \"?\"]\n");
+            pgn.push_str("[Date \"????.??.??\"]\n");
+            pgn.push_str(&format!("[Round \"{}\"]\n", game.round));
+            pgn.push_str(&format!("[White \"{}\"]\n", game.white));
+            pgn.push_str(&format!("[Black \"{}\"]\n", game.black));
+            pgn.push_str(&format!("[Result \"{}\"]\n", game.result.notation()));
+            pgn.push_str(&format!("\n{}\n\n", game.result.notation()));
+        }
+        pgn
+    }
+
+    pub fn save_pgn(&self, path: &str) -> Result<(), String> {
+        if fs::write(path, self.to_pgn()).is_err() {
+            return Err(format!("Could not write to {}.", path));
+        }
+        Ok(())
+    }
+
+    // sorted by points, then Buchholz, then Sonneborn-Berger
+    pub fn standings(&self) -> Vec<Standing> {
+        let rounds = self.pairings();
+
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|name| {
+                // Buchholz adds up the opponents' points, Sonneborn-Berger only counts
+                // the ones that were beaten, and half of the ones that were drawn
+                let mut buchholz = 0.0;
+                let mut sonneborn_berger = 0.0;
+                for game in &self.games {
+                    let (opponent, own_score) = if &game.white == name {
+                        (&game.black, game.result.white_score())
+                    } else if &game.black == name {
+                        (&game.white, 1.0 - game.result.white_score())
+                    } else {
+                        continue;
+                    };
+                    let opponent_points = self.points(opponent, &rounds);
+                    buchholz += opponent_points;
+                    sonneborn_berger += own_score * opponent_points;
+                }
+
+                Standing {
+                    name: name.clone(),
+                    points: self.points(name, &rounds),
+                    buchholz,
+                    sonneborn_berger,
+                }
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            b.points
+                .total_cmp(&a.points)
+                .then(b.buchholz.total_cmp(&a.buchholz))
+                .then(b.sonneborn_berger.total_cmp(&a.sonneborn_berger))
+        });
+        standings
+    }
+
+    // points from the games in the given rounds, where a Swiss bye counts as a win
+    fn points(&self, name: &str, rounds: &[Vec<Pairing>]) -> f64 {
+        let mut points = 0.0;
+        for game in &self.games {
+            if game.round as usize > rounds.len() {
+                continue;
+            }
+            if game.white == name {
+                points += game.result.white_score();
+            } else if game.black == name {
+                points += 1.0 - game.result.white_score();
+            }
+        }
+
+        if let Format::Swiss(_) = self.format {
+            let byes = rounds
+                .iter()
+                .flatten()
+                .filter(|pairing| pairing.black.is_none() && pairing.white == name)
+                .count();
+            points += byes as f64;
+        }
+        points
+    }
+
+    // the circle method: the first player stays put and everyone else moves round one seat a round
+    fn round_robin_pairings(&self, round: u32) -> Vec<Pairing> {
+        let mut seats: Vec<Option<&String>> = self.players.iter().map(Some).collect();
+        if !seats.len().is_multiple_of(2) {
+            seats.push(None);
+        }
+        let seat_count = seats.len();
+        seats[1..].rotate_right((round as usize - 1) % (seat_count - 1));
+
+        let mut pairings: Vec<Pairing> = Vec::new();
+        for (index, &first) in seats[..seat_count / 2].iter().enumerate() {
+            let second = seats[seat_count - 1 - index];
+            // the fixed seat swaps colors every round and the other tables take turns,
+            // so nobody gets the same color more than twice in a row
+            let first_is_white = if index == 0 {
+                !round.is_multiple_of(2)
+            } else {
+                index.is_multiple_of(2)
+            };
+            let (white, black) = if first_is_white {
+                (first, second)
+            } else {
+                (second, first)
+            };
+
+            match (white, black) {
+                (Some(white), Some(black)) => pairings.push(Pairing {
+                    white: white.clone(),
+                    black: Some(black.clone()),
+                }),
+                (Some(player), None) | (None, Some(player)) => pairings.push(Pairing {
+                    white: player.clone(),
+                    black: None,
+                }),
+                (None, None) => {}
+            }
+        }
+        pairings
+    }
+
+    fn swiss_pairings(&self, previous: &[Vec<Pairing>]) -> Vec<Pairing> {
+        let have_played = |a: &str, b: &str| {
+            previous.iter().flatten().any(|pairing| {
+                let black = pairing.black.as_deref();
+                (pairing.white == a && black == Some(b)) || (pairing.white == b && black == Some(a))
+            })
+        };
+        let whites = |name: &str| {
+            previous
+                .iter()
+                .flatten()
+                .filter(|pairing| pairing.white == name && pairing.black.is_some())
+                .count()
+        };
+
+        // highest score first, sorting is stable so seeding breaks ties
+        let mut order: Vec<&String> = self.players.iter().collect();
+        order.sort_by(|a, b| {
+            self.points(b, previous)
+                .total_cmp(&self.points(a, previous))
+        });
+
+        // with an odd number of players, the lowest placed one without a bye sits out
+        let mut bye = None;
+        if !order.len().is_multiple_of(2) {
+            let had_bye = |name: &str| {
+                previous
+                    .iter()
+                    .flatten()
+                    .any(|pairing| pairing.white == name && pairing.black.is_none())
+            };
+            let index = order
+                .iter()
+                .rposition(|name| !had_bye(name))
+                .unwrap_or(order.len() - 1);
+            bye = Some(order.remove(index));
+        }
+
+        // only if everyone left has already played each other
+        let pairs = pair_up(&order, &have_played)
+            .unwrap_or_else(|| order.chunks(2).map(|pair| (pair[0], pair[1])).collect());
+
+        let mut pairings: Vec<Pairing> = Vec::new();
+        for (higher, lower) in pairs {
+            // whoever has had white fewer times gets it, and the higher placed player if that's even
+            let (white, black) = if whites(lower) < whites(higher) {
+                (lower, higher)
+            } else {
+                (higher, lower)
+            };
+            pairings.push(Pairing {
+                white: white.clone(),
+                black: Some(black.clone()),
+            });
+        }
+        if let Some(player) = bye {
+            pairings.push(Pairing {
+                white: player.clone(),
+                black: None,
+            });
+        }
+        pairings
+    }
+}
+
+// what both creating and loading a tournament check
+fn check_entries(format: Format, players: &[String]) -> Result<(), String> {
+    if players.len() < 2 {
+        return Err(String::from("A tournament needs at least 2 players."));
+    }
+    for (index, player) in players.iter().enumerate() {
+        check_player_name(player)?;
+        if players[..index].contains(player) {
+            return Err(format!("{} is entered more than once.", player));
+        }
+    }
+    if let Format::Swiss(rounds) = format {
+        // any more and someone would have to play the same opponent twice
+        if rounds == 0 || rounds as usize >= players.len() {
+            return Err(String::from(
+                "A Swiss tournament needs at least 1 round, and fewer rounds than players.",
+            ));
+        }
+    }
+    Ok(())
+}
+
+// pairs each player with the highest placed player after them that they haven't played yet,
+// going back to try someone else when that leaves a player with no one to play
+fn pair_up<'a>(
+    players: &[&'a String],
+    have_played: &dyn Fn(&str, &str) -> bool,
+) -> Option<Vec<(&'a String, &'a String)>> {
+    let (first, rest) = match players.split_first() {
+        Some(split) => split,
+        None => return Some(Vec::new()),
+    };
+
+    for (index, &opponent) in rest.iter().enumerate() {
+        if have_played(first, opponent) {
+            continue;
+        }
+        let mut others = rest.to_vec();
+        others.remove(index);
+        if let Some(mut pairs) = pair_up(&others, have_played) {
+            pairs.insert(0, (*first, opponent));
+            return Some(pairs);
+        }
+    }
+    None
+}
+
+pub fn print_round(tournament: &Tournament) {
+    let round = match tournament.current_round() {
+        Some(round) => round,
+        None => {
+            println!("The tournament is over.");
+            print_standings(tournament);
+            return;
+        }
+    };
+
+    println!("Round {} of {}:", round, tournament.round_count());
+    for pairing in &tournament.pairings()[round as usize - 1] {
+        match &pairing.black {
+            Some(black) => match tournament.game(round, &pairing.white, black) {
+                Some(game) => println!("{} - {} {}", pairing.white, black, game.result.notation()),
+                None => println!("{} - {}", pairing.white, black),
+            },
+            None => println!("{} has a bye", pairing.white),
+        }
+    }
+    println!();
+}
+
+pub fn print_standings(tournament: &Tournament) {
+    println!(
+        "{:<4} {:<20} {:>6} {:>9} {:>6}",
+        "#", "Player", "Points", "Buchholz", "SB"
+    );
+    for (rank, standing) in tournament.standings().iter().enumerate() {
+        println!(
+            "{:<4} {:<20} {:>6.1} {:>9.1} {:>6.2}",
+            rank + 1,
+            standing.name,
+            standing.points,
+            standing.buchholz,
+            standing.sonneborn_berger
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tournament(format: Format, players: &[&str]) -> Tournament {
+        Tournament {
+            format,
+            players: players.iter().map(|player| player.to_string()).collect(),
+            games: Vec::new(),
+            path: String::new(),
+        }
+    }
+
+    // plays every game of the current round, with white always winning
+    fn play_round(tournament: &mut Tournament) {
+        let round = tournament.current_round().unwrap();
+        for pairing in &tournament.pairings()[round as usize - 1] {
+            if let Some(black) = &pairing.black {
+                tournament.games.push(TournamentGame {
+                    round,
+                    white: pairing.white.clone(),
+                    black: black.clone(),
+                    result: GameResult::WhiteWins,
+                });
+            }
+        }
+    }
+
+    fn played(tournament: &Tournament, a: &str, b: &str) -> usize {
+        tournament
+            .games
+            .iter()
+            .filter(|game| {
+                (game.white == a && game.black == b) || (game.white == b && game.black == a)
+            })
+            .count()
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        for players in [vec!["a", "b", "c", "d"], vec!["a", "b", "c", "d", "e"]] {
+            let mut tournament = tournament(Format::RoundRobin, &players);
+            while tournament.current_round().is_some() {
+                play_round(&mut tournament);
+            }
+            for (index, a) in players.iter().enumerate() {
+                for b in &players[index + 1..] {
+                    assert_eq!(played(&tournament, a, b), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn swiss_avoids_rematches_and_repeat_byes() {
+        let players = ["a", "b", "c", "d", "e"];
+        let mut tournament = tournament(Format::Swiss(4), &players);
+        while tournament.current_round().is_some() {
+            play_round(&mut tournament);
+        }
+        for (index, a) in players.iter().enumerate() {
+            for b in &players[index + 1..] {
+                assert!(played(&tournament, a, b) <= 1);
+            }
+        }
+
+        let byes: Vec<String> = tournament
+            .pairings()
+            .iter()
+            .flatten()
+            .filter(|pairing| pairing.black.is_none())
+            .map(|pairing| pairing.white.clone())
+            .collect();
+        assert_eq!(byes.len(), 4);
+        for (index, player) in byes.iter().enumerate() {
+            assert!(!byes[index + 1..].contains(player));
+        }
+    }
+
+    #[test]
+    fn loading_checks_the_entries() {
+        let path =
+            std::env::temp_dir().join(format!("rust-chess-tournament-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        for history in [
+            "swiss 0\na b c\n",
+            "swiss 3\na b c\n",
+            "roundrobin\na\n",
+            "roundrobin\na b a\n",
+        ] {
+            fs::write(path, history).unwrap();
+            assert!(Tournament::load(path).is_err());
+        }
+
+        fs::write(path, "swiss 2\na b c\n1 a b 1-0\n").unwrap();
+        let tournament = Tournament::load(path).unwrap();
+        assert_eq!(tournament.format, Format::Swiss(2));
+        assert_eq!(tournament.games.len(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pgn_has_a_game_for_every_result() {
+        let mut tournament = tournament(Format::RoundRobin, &["a", "b", "c"]);
+        play_round(&mut tournament);
+
+        // the bye isn't a game
+        assert_eq!(
+            tournament.to_pgn(),
+            "[Event \"Round-robin tournament\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"1\"]\n[White \"c\"]\n[Black \"b\"]\n[Result \"1-0\"]\n\n1-0\n\n"
+        );
+    }
+
+    #[test]
+    fn standings_use_buchholz_and_sonneborn_berger() {
+        let mut tournament = tournament(Format::RoundRobin, &["a", "b", "c", "d"]);
+        // the winner of each game, or None for a draw
+        let winner = |a: &str, b: &str| match (a.min(b), a.max(b)) {
+            ("a", "b") | ("b", "c") => None,
+            ("a", "c") | ("c", "d") => Some("c"),
+            ("a", "d") => Some("a"),
+            _ => Some("b"),
+        };
+        while let Some(round) = tournament.current_round() {
+            for pairing in &tournament.pairings()[round as usize - 1] {
+                let black = pairing.black.clone().unwrap();
+                let result = match winner(&pairing.white, &black) {
+                    None => GameResult::Draw,
+                    Some(winner) if winner == pairing.white => GameResult::WhiteWins,
+                    Some(_) => GameResult::BlackWins,
+                };
+                tournament.games.push(TournamentGame {
+                    round,
+                    white: pairing.white.clone(),
+                    black,
+                    result,
+                });
+            }
+        }
+
+        // c: 2.5 points, b: 2, a: 1.5, d: 0
+        let standings = tournament.standings();
+        let names: Vec<&str> = standings
+            .iter()
+            .map(|standing| standing.name.as_str())
+            .collect();
+        assert_eq!(names, ["c", "b", "a", "d"]);
+        // b drew with c and a, and beat d
+        assert_eq!(standings[1].buchholz, 2.5 + 1.5 + 0.0);
+        assert_eq!(standings[1].sonneborn_berger, 1.25 + 0.75 + 0.0);
+    }
+}
@@ -0,0 +1,655 @@
+use crate::board::{create_board, create_board_from_fen, Board};
+use crate::piece::{Piece, PieceType};
+use crate::{Color, GameResult};
+
+// the rules that change between variants, everything else is handled by the board and pieces
+pub trait Variant {
+    fn name(&self) -> &str;
+
+    fn create_board(&self) -> Board {
+        create_board()
+    }
+
+    // whether kings can be in check at all
+    fn has_check(&self) -> bool {
+        true
+    }
+
+    // extra rules on top of how the pieces move, with the reason a move isn't allowed
+    fn check_move(&self, board: &Board, from: (u8, u8), to: (u8, u8)) -> Result<(), String> {
+        if leaves_king_in_check(board, from, to) {
+            return Err(String::from("That would leave your king in check."));
+        }
+        Ok(())
+    }
+
+    // whether captured pieces can be dropped back onto the board
+    fn has_drops(&self) -> bool {
+        false
+    }
+
+    // whether kings can castle, boards from a FEN or Chess960 lose their castling rights otherwise
+    fn has_castling(&self) -> bool {
+        true
+    }
+
+    // whether white pawns on the first rank can move two squares, as well as the ones on the second
+    fn has_first_rank_double_steps(&self) -> bool {
+        false
+    }
+
+    fn check_drop(&self, _board: &Board, _piece: &Piece, _to: (u8, u8)) -> Result<(), String> {
+        Err(format!("Pieces can't be dropped in {}.", self.name()))
+    }
+
+    fn can_promote_to(&self, piece_type: &PieceType) -> bool {
+        matches!(
+            piece_type,
+            PieceType::Knight | PieceType::Bishop | PieceType::Rook | PieceType::Queen
+        )
+    }
+
+    // called after a move that took a piece, with the square it happened on
+    fn after_capture(&self, _board: &mut Board, _square: (u8, u8)) {}
+
+    // called after every move, returns the result once the game is over
+    fn game_over(&mut self, _board: &Board) -> Option<GameResult> {
+        None
+    }
+
+    // whether a color could still win, which decides if the opponent running out of time loses or draws
+    fn can_still_win(&self, board: &Board, color: Color) -> bool {
+        board.has_mating_material(color)
+    }
+
+    // anything extra to show under the board, like how many checks have been given
+    fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+pub fn variant_from_name(name: &str) -> Option<Box<dyn Variant>> {
+    match name {
+        "standard" => Some(Box::new(Standard)),
+        "threecheck" => Some(Box::new(ThreeCheck {
+            white_checks: 0,
+            black_checks: 0,
+        })),
+        "kingofthehill" => Some(Box::new(KingOfTheHill)),
+        "racingkings" => Some(Box::new(RacingKings {
+            white_finished: false,
+        })),
+        "crazyhouse" => Some(Box::new(Crazyhouse)),
+        "atomic" => Some(Box::new(Atomic)),
+        "antichess" => Some(Box::new(Antichess)),
+        "horde" => Some(Box::new(Horde)),
+        _ => None,
+    }
+}
+
+pub fn leaves_king_in_check(board: &Board, from: (u8, u8), to: (u8, u8)) -> bool {
+    let color = board.squares[from.0 as usize][from.1 as usize].piece.color;
+    board.with_move(from, to).is_in_check(color)
+}
+
+// the color that made the last move
+fn last_mover(board: &Board) -> Color {
+    enemy_of(board.current_turn)
+}
+
+fn enemy_of(color: Color) -> Color {
+    if color == Color::White {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+fn win_for(color: Color) -> GameResult {
+    if color == Color::White {
+        GameResult::WhiteWins
+    } else {
+        GameResult::BlackWins
+    }
+}
+
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &str {
+        "Standard"
+    }
+}
+
+// giving check three times wins
+pub struct ThreeCheck {
+    pub white_checks: u8,
+    pub black_checks: u8,
+}
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &str {
+        "Three-check"
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        if !board.is_in_check(board.current_turn) {
+            return None;
+        }
+
+        let mover = last_mover(board);
+        let checks = if mover == Color::White {
+            &mut self.white_checks
+        } else {
+            &mut self.black_checks
+        };
+        *checks += 1;
+
+        if *checks == 3 {
+            Some(win_for(mover))
+        } else {
+            None
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "Checks given: White {}, Black {}",
+            self.white_checks, self.black_checks
+        ))
+    }
+}
+
+// getting the king to one of the four center squares wins
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &str {
+        "King of the Hill"
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        let mover = last_mover(board);
+        let on_hill = board
+            .king_squares(mover)
+            .iter()
+            .any(|&(row, col)| (3..=4).contains(&row) && (3..=4).contains(&col));
+
+        if on_hill {
+            Some(win_for(mover))
+        } else {
+            None
+        }
+    }
+
+    // a lone king can still walk to the hill
+    fn can_still_win(&self, _board: &Board, _color: Color) -> bool {
+        true
+    }
+}
+
+// both kings race to the 8th rank, and nobody is ever allowed to give check
+pub struct RacingKings {
+    // white got there first, but black still gets one more move to draw
+    pub white_finished: bool,
+}
+
+impl Variant for RacingKings {
+    fn name(&self) -> &str {
+        "Racing Kings"
+    }
+
+    fn create_board(&self) -> Board {
+        match create_board_from_fen("8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1") {
+            Ok(board) => board,
+            Err(_err) => create_board(),
+        }
+    }
+
+    fn check_move(&self, board: &Board, from: (u8, u8), to: (u8, u8)) -> Result<(), String> {
+        if leaves_king_in_check(board, from, to) {
+            return Err(String::from("That would leave your king in check."));
+        }
+
+        let color = board.squares[from.0 as usize][from.1 as usize].piece.color;
+        if board.with_move(from, to).is_in_check(enemy_of(color)) {
+            return Err(String::from("Giving check isn't allowed in Racing Kings."));
+        }
+        Ok(())
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        let finished = |color: Color| board.king_squares(color).iter().any(|&(row, _)| row == 7);
+
+        if self.white_finished {
+            return if finished(Color::Black) {
+                Some(GameResult::Draw)
+            } else {
+                Some(GameResult::WhiteWins)
+            };
+        }
+
+        if finished(Color::Black) {
+            return Some(GameResult::BlackWins);
+        }
+
+        if finished(Color::White) {
+            // black can only still draw if its king is one step away
+            let black_can_follow = board
+                .king_squares(Color::Black)
+                .iter()
+                .any(|&(row, _)| row == 6);
+            if black_can_follow {
+                self.white_finished = true;
+                return None;
+            }
+            return Some(GameResult::WhiteWins);
+        }
+
+        None
+    }
+
+    // a lone king can still win the race
+    fn can_still_win(&self, _board: &Board, _color: Color) -> bool {
+        true
+    }
+}
+
+// captured pieces change sides and go into a pocket, and can be dropped back on instead of moving
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &str {
+        "Crazyhouse"
+    }
+
+    fn has_drops(&self) -> bool {
+        true
+    }
+
+    fn check_drop(&self, board: &Board, piece: &Piece, to: (u8, u8)) -> Result<(), String> {
+        if board.with_drop(piece, to).is_in_check(piece.color) {
+            return Err(String::from("That would leave your king in check."));
+        }
+        Ok(())
+    }
+
+    // anything either side has left can be captured and dropped again, so it's only a draw with bare kings
+    fn can_still_win(&self, board: &Board, _color: Color) -> bool {
+        let on_board = board.squares.iter().any(|row| {
+            row.iter()
+                .any(|square| !matches!(square.piece.piece_type, PieceType::None | PieceType::King))
+        });
+        on_board || !board.white_pocket.is_empty() || !board.black_pocket.is_empty()
+    }
+}
+
+// every capture explodes, taking out the capturing piece and every piece around it except pawns
+pub struct Atomic;
+
+// removes the piece on the square and every non-pawn piece next to it
+pub fn explode(board: &mut Board, square: (u8, u8)) {
+    let (row, col) = (square.0 as i8, square.1 as i8);
+
+    for blast_row in row - 1..=row + 1 {
+        for blast_col in col - 1..=col + 1 {
+            if !(0..8).contains(&blast_row) || !(0..8).contains(&blast_col) {
+                continue;
+            }
+            let blast_square = (blast_row as u8, blast_col as u8);
+            let piece = &board.squares[blast_row as usize][blast_col as usize].piece;
+            if piece.piece_type == PieceType::Pawn && blast_square != square {
+                continue;
+            }
+
+            board.squares[blast_row as usize][blast_col as usize].piece = Piece {
+                piece_type: PieceType::None,
+                color: Color::None,
+            };
+            board.castling_rooks.retain(|&rook| rook != blast_square);
+            board
+                .promoted_squares
+                .retain(|&promoted| promoted != blast_square);
+        }
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &str {
+        "Atomic"
+    }
+
+    fn check_move(&self, board: &Board, from: (u8, u8), to: (u8, u8)) -> Result<(), String> {
+        let piece = &board.squares[from.0 as usize][from.1 as usize].piece;
+        let target = &board.squares[to.0 as usize][to.1 as usize].piece;
+
+        // moving nothing is left for the pieces to complain about
+        if piece.piece_type == PieceType::None {
+            return Ok(());
+        }
+
+        let color = piece.color;
+        let enemy = enemy_of(color);
+        let capture = target.piece_type != PieceType::None && target.color == enemy;
+
+        if piece.piece_type == PieceType::King && capture {
+            return Err(String::from("Kings can't capture in Atomic."));
+        }
+
+        let mut after = board.with_move(from, to);
+        if capture {
+            explode(&mut after, to);
+        }
+
+        if after.king_squares(color).is_empty() {
+            return Err(String::from("That would blow up your own king."));
+        }
+        // blowing up the enemy king wins straight away, whatever else is going on
+        if after.king_squares(enemy).is_empty() {
+            return Ok(());
+        }
+        // kings next to each other can't be in check, since taking a king would blow up the other one
+        let kings_touching = after.king_squares(color).iter().any(|&(row, col)| {
+            after
+                .king_squares(enemy)
+                .iter()
+                .any(|&(enemy_row, enemy_col)| {
+                    row.abs_diff(enemy_row) <= 1 && col.abs_diff(enemy_col) <= 1
+                })
+        });
+        if !kings_touching && after.is_in_check(color) {
+            return Err(String::from("That would leave your king in check."));
+        }
+        Ok(())
+    }
+
+    fn after_capture(&self, board: &mut Board, square: (u8, u8)) {
+        explode(board, square);
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        let mover = last_mover(board);
+        if board.king_squares(enemy_of(mover)).is_empty() {
+            Some(win_for(mover))
+        } else {
+            None
+        }
+    }
+}
+
+// whoever loses all their pieces, or has no moves left, wins, and taking is compulsory
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &str {
+        "Antichess"
+    }
+
+    fn has_check(&self) -> bool {
+        false
+    }
+
+    // the king is just another piece, so there's no castling
+    fn has_castling(&self) -> bool {
+        false
+    }
+
+    fn check_move(&self, board: &Board, _from: (u8, u8), to: (u8, u8)) -> Result<(), String> {
+        let is_capture = |to: (u8, u8)| {
+            let target = &board.squares[to.0 as usize][to.1 as usize].piece;
+            target.piece_type != PieceType::None && target.color != board.current_turn
+        };
+
+        if !is_capture(to)
+            && board
+                .possible_moves()
+                .iter()
+                .any(|&(_, possible_to)| is_capture(possible_to))
+        {
+            return Err(String::from(
+                "You have to take a piece when you can in Antichess.",
+            ));
+        }
+        Ok(())
+    }
+
+    fn can_promote_to(&self, piece_type: &PieceType) -> bool {
+        matches!(
+            piece_type,
+            PieceType::Knight
+                | PieceType::Bishop
+                | PieceType::Rook
+                | PieceType::Queen
+                | PieceType::King
+        )
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        // the player to move wins if they have nothing left, or can't move what they have
+        let to_move = board.current_turn;
+        if board.possible_moves().is_empty() {
+            Some(win_for(to_move))
+        } else {
+            None
+        }
+    }
+
+    // giving everything away is always possible
+    fn can_still_win(&self, _board: &Board, _color: Color) -> bool {
+        true
+    }
+}
+
+// white has a horde of 36 pawns and no king, and black wins by taking every one of them
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &str {
+        "Horde"
+    }
+
+    fn create_board(&self) -> Board {
+        match create_board_from_fen(
+            "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+        ) {
+            Ok(board) => board,
+            Err(_err) => create_board(),
+        }
+    }
+
+    fn has_first_rank_double_steps(&self) -> bool {
+        true
+    }
+
+    fn game_over(&mut self, board: &Board) -> Option<GameResult> {
+        let white_left = board.squares.iter().any(|row| {
+            row.iter().any(|square| {
+                square.piece.piece_type != PieceType::None && square.piece.color == Color::White
+            })
+        });
+
+        if white_left {
+            None
+        } else {
+            Some(GameResult::BlackWins)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_check_counts_each_check_until_the_third() {
+        let mut variant = ThreeCheck {
+            white_checks: 0,
+            black_checks: 0,
+        };
+        let quiet = create_board_from_fen("4k3/8/8/8/8/8/8/3R2K1 b - - 0 1").unwrap();
+        let check = create_board_from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+
+        assert_eq!(variant.game_over(&quiet), None);
+        assert_eq!(variant.white_checks, 0);
+        assert_eq!(variant.game_over(&check), None);
+        assert_eq!(variant.game_over(&check), None);
+        assert_eq!((variant.white_checks, variant.black_checks), (2, 0));
+        assert_eq!(variant.game_over(&check), Some(GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn racing_kings_lets_black_follow_for_a_draw() {
+        let mut variant = RacingKings {
+            white_finished: false,
+        };
+        let white_there = create_board_from_fen("K7/5k2/8/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(variant.game_over(&white_there), None);
+        assert!(variant.white_finished);
+        let both_there = create_board_from_fen("K4k2/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(variant.game_over(&both_there), Some(GameResult::Draw));
+
+        // black has one move to get there, and loses if it doesn't
+        let mut variant = RacingKings {
+            white_finished: true,
+        };
+        let black_stayed = create_board_from_fen("K7/8/5k2/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(
+            variant.game_over(&black_stayed),
+            Some(GameResult::WhiteWins)
+        );
+
+        // too far away to follow, so white wins straight away
+        let mut variant = RacingKings {
+            white_finished: false,
+        };
+        assert_eq!(
+            variant.game_over(&black_stayed),
+            Some(GameResult::WhiteWins)
+        );
+    }
+
+    #[test]
+    fn crazyhouse_promoted_pieces_go_back_to_the_pocket_as_pawns() {
+        let mut board = create_board_from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        board.drops = Crazyhouse.has_drops();
+
+        let pawn = board.squares[6][0].piece.clone();
+        let mut board = pawn.move_piece((6, 0), (7, 0), PieceType::Queen, &mut board);
+        assert_eq!(board.promoted_squares, vec![(7, 0)]);
+
+        let rook = board.squares[7][1].piece.clone();
+        let board = rook.move_piece((7, 1), (7, 0), PieceType::None, &mut board);
+        assert_eq!(board.black_pocket, vec![PieceType::Pawn]);
+        assert!(board.promoted_squares.is_empty());
+    }
+
+    #[test]
+    fn crazyhouse_pawns_cant_be_dropped_on_the_first_or_last_rank() {
+        let mut board = create_board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        board.drops = Crazyhouse.has_drops();
+        board.white_pocket.push(PieceType::Pawn);
+        let pawn = Piece {
+            piece_type: PieceType::Pawn,
+            color: Color::White,
+        };
+
+        assert!(!pawn.can_drop((0, 0), &board));
+        assert!(!pawn.can_drop((7, 0), &board));
+        assert!(pawn.can_drop((3, 0), &board));
+    }
+
+    #[test]
+    fn atomic_explosions_spare_pawns() {
+        let board = create_board_from_fen("7k/5b2/2p1n3/3q4/2P1N3/8/8/K2R4 w - - 0 1").unwrap();
+        assert_eq!(Atomic.check_move(&board, (0, 3), (4, 3)), Ok(()));
+
+        let mut after = board.with_move((0, 3), (4, 3));
+        Atomic.after_capture(&mut after, (4, 3));
+        let piece_type = |row: usize, col: usize| after.squares[row][col].piece.piece_type.clone();
+
+        // the capturing rook goes up with everything around it
+        assert_eq!(piece_type(4, 3), PieceType::None);
+        assert_eq!(piece_type(5, 4), PieceType::None);
+        assert_eq!(piece_type(3, 4), PieceType::None);
+        assert_eq!(piece_type(5, 2), PieceType::Pawn);
+        assert_eq!(piece_type(3, 2), PieceType::Pawn);
+        // two squares away is out of range
+        assert_eq!(piece_type(6, 5), PieceType::Bishop);
+    }
+
+    #[test]
+    fn atomic_kings_cant_capture_or_blow_themselves_up() {
+        let board = create_board_from_fen("4k3/8/8/8/8/8/3p4/3RK3 w - - 0 1").unwrap();
+        assert_eq!(
+            Atomic.check_move(&board, (0, 4), (1, 3)),
+            Err(String::from("Kings can't capture in Atomic."))
+        );
+        assert_eq!(
+            Atomic.check_move(&board, (0, 3), (1, 3)),
+            Err(String::from("That would blow up your own king."))
+        );
+    }
+
+    #[test]
+    fn atomic_kings_next_to_each_other_ignore_check() {
+        // the a2 rook attacks the white king, but it's touching the black one
+        let board = create_board_from_fen("8/8/8/8/8/3k3P/r3K3/8 w - - 0 1").unwrap();
+        assert_eq!(Atomic.check_move(&board, (2, 7), (3, 7)), Ok(()));
+        assert_eq!(
+            Atomic.check_move(&board, (1, 4), (1, 5)),
+            Err(String::from("That would leave your king in check."))
+        );
+    }
+
+    #[test]
+    fn antichess_forces_captures() {
+        let board = create_board_from_fen("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            Antichess.check_move(&board, (0, 4), (0, 5)),
+            Err(String::from(
+                "You have to take a piece when you can in Antichess."
+            ))
+        );
+        assert_eq!(Antichess.check_move(&board, (0, 4), (1, 3)), Ok(()));
+    }
+
+    #[test]
+    fn antichess_is_won_with_nothing_left_to_move() {
+        let nothing_left = create_board_from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(
+            Antichess.game_over(&nothing_left),
+            Some(GameResult::WhiteWins)
+        );
+
+        let blocked = create_board_from_fen("4k3/8/8/8/8/p7/P7/8 w - - 0 1").unwrap();
+        assert_eq!(Antichess.game_over(&blocked), Some(GameResult::WhiteWins));
+
+        let playing_on = create_board_from_fen("4k3/8/8/8/8/8/P7/8 w - - 0 1").unwrap();
+        assert_eq!(Antichess.game_over(&playing_on), None);
+    }
+
+    #[test]
+    fn horde_is_won_by_taking_every_white_pawn() {
+        let pawn_left = create_board_from_fen("4k3/8/8/8/8/8/P7/8 b - - 0 1").unwrap();
+        assert_eq!(Horde.game_over(&pawn_left), None);
+
+        let all_taken = create_board_from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(Horde.game_over(&all_taken), Some(GameResult::BlackWins));
+    }
+
+    #[test]
+    fn horde_pawns_double_step_from_the_first_rank_for_white_only() {
+        let mut board = create_board_from_fen("p3k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap();
+        let white_pawn = board.squares[0][0].piece.clone();
+        assert!(!white_pawn.can_move((0, 0), (2, 0), &board));
+
+        board.first_rank_double_steps = Horde.has_first_rank_double_steps();
+        assert!(white_pawn.can_move((0, 0), (2, 0), &board));
+
+        // a black pawn on the eighth rank still only moves one square
+        board.current_turn = Color::Black;
+        let black_pawn = board.squares[7][0].piece.clone();
+        assert!(black_pawn.can_move((7, 0), (6, 0), &board));
+        assert!(!black_pawn.can_move((7, 0), (5, 0), &board));
+    }
+}